@@ -0,0 +1,136 @@
+#![no_std]
+
+//! LuckyStake Factory Contract
+//!
+//! Deploys LuckyStake pools at deterministic addresses and keeps a registry of them.
+//! The pool Wasm hash is fixed at factory initialization; every pool is deployed with
+//! `deployer().with_current_contract(salt)` and initialized in the same invocation,
+//! so a pool can never be observed uninitialized. At most one pool is registered per
+//! `period_days`, which lets the frontend look pools up instead of hardcoding them.
+//!
+//! Each registered pool's fingerprint (see `lucky_stake_interface`) is recorded at
+//! deployment, so other contracts can ask `is_pool` whether an address is a pool this
+//! factory deployed and that it still answers with the same fingerprint.
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, Address, BytesN, Env, IntoVal, Symbol, Val, Vec,
+};
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    PoolWasmHash,
+    Pools,
+    PoolByPeriod(u32),
+    Fingerprint(Address),
+}
+
+/// Arguments forwarded to the pool's `initialize`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InitConfig {
+    pub admin: Address,
+    pub token: Address,
+    pub period_days: u32,
+}
+
+#[contract]
+pub struct LuckyStakeFactory;
+
+#[contractimpl]
+impl LuckyStakeFactory {
+    pub fn initialize(env: Env, admin: Address, pool_wasm_hash: BytesN<32>) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("already initialised");
+        }
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::PoolWasmHash, &pool_wasm_hash);
+        env.storage().instance().set(&DataKey::Pools, &Vec::<Address>::new(&env));
+    }
+
+    /// Deploy a pool at the address derived from this factory and `salt`, initialize it
+    /// with `config` and register it under `config.period_days`.
+    pub fn deploy_pool(env: Env, salt: BytesN<32>, config: InitConfig) -> Address {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic!("not initialised"));
+        admin.require_auth();
+
+        let period_key = DataKey::PoolByPeriod(config.period_days);
+        assert!(
+            !env.storage().instance().has(&period_key),
+            "pool for this period already deployed"
+        );
+
+        let wasm_hash: BytesN<32> = env.storage().instance().get(&DataKey::PoolWasmHash).unwrap();
+        let pool = env.deployer().with_current_contract(salt).deploy(wasm_hash);
+
+        let args: Vec<Val> = soroban_sdk::vec![
+            &env,
+            config.admin.into_val(&env),
+            config.token.into_val(&env),
+            config.period_days.into_val(&env),
+        ];
+        env.invoke_contract::<()>(&pool, &Symbol::new(&env, "initialize"), args);
+        let fingerprint = lucky_stake_interface::pool_fingerprint(&env, &config.token, config.period_days);
+        lucky_stake_interface::assert_is_pool(&env, &pool, &fingerprint);
+
+        let mut pools = Self::get_pools(env.clone());
+        pools.push_back(pool.clone());
+        env.storage().instance().set(&DataKey::Pools, &pools);
+        env.storage().instance().set(&period_key, &pool);
+        env.storage().instance().set(&DataKey::Fingerprint(pool.clone()), &fingerprint);
+
+        env.events().publish(
+            (Symbol::new(&env, "pool_deployed"), config.period_days),
+            pool.clone(),
+        );
+        pool
+    }
+
+    pub fn get_pools(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Pools)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_pool_by_period(env: Env, days: u32) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PoolByPeriod(days))
+    }
+
+    /// Fingerprint recorded when `pool` was deployed, if this factory deployed it.
+    pub fn get_pool_fingerprint(env: Env, pool: Address) -> Option<BytesN<32>> {
+        env.storage().instance().get(&DataKey::Fingerprint(pool))
+    }
+
+    /// Whether `pool` was deployed by this factory and still reports the fingerprint
+    /// recorded then.
+    pub fn is_pool(env: Env, pool: Address) -> bool {
+        match Self::get_pool_fingerprint(env.clone(), pool.clone()) {
+            Some(fingerprint) => lucky_stake_interface::is_pool(&env, &pool, &fingerprint),
+            None => false,
+        }
+    }
+
+    pub fn get_pool_wasm_hash(env: Env) -> BytesN<32> {
+        env.storage()
+            .instance()
+            .get(&DataKey::PoolWasmHash)
+            .unwrap_or_else(|| panic!("not initialised"))
+    }
+
+    pub fn get_admin(env: Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic!("not initialised"))
+    }
+}
+
+#[cfg(test)]
+mod test;
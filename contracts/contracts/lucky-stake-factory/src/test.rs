@@ -0,0 +1,226 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{
+    testutils::{Address as _, EnvTestConfig},
+    token, Address, BytesN, Env, IntoVal, Symbol, Val, Vec,
+};
+
+/// The pool as released: `cargo build --release --target wasm32v1-none -p
+/// lucky-stake-pool` has to run before these tests.
+mod pool {
+    soroban_sdk::contractimport!(file = "../../target/wasm32v1-none/release/lucky_stake_pool.wasm");
+}
+
+use pool::Client as LuckyStakePoolClient;
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  Helpers
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Returns (env, factory_id, admin, token_id)
+fn setup() -> (Env, Address, Address, Address) {
+    // Snapshots would embed the whole pool Wasm and churn with every pool change
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    env.mock_all_auths();
+    // Instantiating the real pool Wasm costs more than the default test budget
+    env.budget().reset_unlimited();
+
+    let token_admin = Address::generate(&env);
+    let token_id = env
+        .register_stellar_asset_contract_v2(token_admin)
+        .address();
+
+    let wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let admin = Address::generate(&env);
+    let factory_id = env.register_contract(None, LuckyStakeFactory);
+    LuckyStakeFactoryClient::new(&env, &factory_id).initialize(&admin, &wasm_hash);
+
+    (env, factory_id, admin, token_id)
+}
+
+/// Names in the export section of a Wasm module.
+fn wasm_exports(wasm: &[u8]) -> std::vec::Vec<&str> {
+    fn leb(wasm: &[u8], at: &mut usize) -> usize {
+        let (mut value, mut shift) = (0usize, 0);
+        loop {
+            let byte = wasm[*at];
+            *at += 1;
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    let mut at = 8; // magic and version
+    while at < wasm.len() {
+        let id = wasm[at];
+        at += 1;
+        let size = leb(wasm, &mut at);
+        if id != 7 {
+            at += size;
+            continue;
+        }
+        let mut names = std::vec::Vec::new();
+        for _ in 0..leb(wasm, &mut at) {
+            let len = leb(wasm, &mut at);
+            names.push(core::str::from_utf8(&wasm[at..at + len]).unwrap());
+            at += len + 1; // name and export kind
+            leb(wasm, &mut at);
+        }
+        return names;
+    }
+    std::vec::Vec::new()
+}
+
+fn deploy(env: &Env, factory_id: &Address, admin: &Address, token_id: &Address, period_days: u32) -> Address {
+    let salt = BytesN::from_array(env, &[period_days as u8; 32]);
+    LuckyStakeFactoryClient::new(env, factory_id).deploy_pool(
+        &salt,
+        &InitConfig {
+            admin: admin.clone(),
+            token: token_id.clone(),
+            period_days,
+        },
+    )
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  deploy_pool
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_deploy_pools_for_every_period() {
+    let (env, factory_id, admin, token_id) = setup();
+    let factory = LuckyStakeFactoryClient::new(&env, &factory_id);
+
+    let weekly = deploy(&env, &factory_id, &admin, &token_id, 7);
+    let biweekly = deploy(&env, &factory_id, &admin, &token_id, 15);
+    let monthly = deploy(&env, &factory_id, &admin, &token_id, 30);
+
+    assert_eq!(factory.get_pools().len(), 3);
+    assert_eq!(factory.get_pool_by_period(&7), Some(weekly.clone()));
+    assert_eq!(factory.get_pool_by_period(&15), Some(biweekly.clone()));
+    assert_eq!(factory.get_pool_by_period(&30), Some(monthly.clone()));
+    assert_eq!(factory.get_pool_by_period(&14), None);
+
+    for (pool, days) in [(weekly, 7u32), (biweekly, 15), (monthly, 30)] {
+        let c = LuckyStakePoolClient::new(&env, &pool);
+        assert!(c.is_initialized());
+        assert_eq!(c.get_period_days(), days);
+        assert_eq!(c.get_admin(), admin);
+        assert_eq!(c.get_token(), token_id);
+    }
+}
+
+#[test]
+fn test_deployed_address_is_deterministic() {
+    let (env, factory_id, admin, token_id) = setup();
+    let salt = BytesN::from_array(&env, &[7u8; 32]);
+    let expected = env
+        .deployer()
+        .with_address(factory_id.clone(), salt)
+        .deployed_address();
+
+    assert_eq!(deploy(&env, &factory_id, &admin, &token_id, 7), expected);
+}
+
+#[test]
+fn test_deployed_pool_accepts_deposits() {
+    let (env, factory_id, admin, token_id) = setup();
+    let pool = deploy(&env, &factory_id, &admin, &token_id, 7);
+    let c = LuckyStakePoolClient::new(&env, &pool);
+
+    let user = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token_id).mint(&user, &1_000_000_000i128);
+    c.deposit(&user, &1_000_000_000i128);
+
+    assert_eq!(c.get_balance(&user), 1_000_000_000i128);
+    assert_eq!(c.get_tickets(&user), 7_000_000_000i128);
+}
+
+#[test]
+fn test_registry_records_pool_fingerprints() {
+    let (env, factory_id, admin, token_id) = setup();
+    let factory = LuckyStakeFactoryClient::new(&env, &factory_id);
+
+    for days in [7u32, 15, 30] {
+        let pool = deploy(&env, &factory_id, &admin, &token_id, days);
+        let fingerprint = LuckyStakePoolClient::new(&env, &pool).get_pool_fingerprint();
+        assert_eq!(fingerprint, lucky_stake_interface::pool_fingerprint(&env, &token_id, days));
+        assert_eq!(factory.get_pool_fingerprint(&pool), Some(fingerprint));
+        assert!(factory.is_pool(&pool));
+    }
+}
+
+#[test]
+fn test_impostor_pool_is_not_recognised() {
+    let (env, factory_id, admin, token_id) = setup();
+    let factory = LuckyStakeFactoryClient::new(&env, &factory_id);
+    let weekly = deploy(&env, &factory_id, &admin, &token_id, 7);
+    let registered = factory.get_pool_fingerprint(&weekly).unwrap();
+
+    // A genuine pool binary deployed by hand, but for a different token
+    let other_token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let impostor = env.register_contract_wasm(None, pool::WASM);
+    LuckyStakePoolClient::new(&env, &impostor).initialize(&admin, &other_token, &7);
+
+    assert_ne!(LuckyStakePoolClient::new(&env, &impostor).get_pool_fingerprint(), registered);
+    assert!(!lucky_stake_interface::is_pool(&env, &impostor, &registered));
+    assert!(!factory.is_pool(&impostor));
+    assert_eq!(factory.get_pool_fingerprint(&impostor), None);
+
+    // Neither is an arbitrary contract
+    assert!(!lucky_stake_interface::is_pool(&env, &token_id, &registered));
+}
+
+/// The imported Wasm is the release build, without the `deterministic-draws`
+/// feature, so the deterministic-draw entrypoint must be missing.
+#[test]
+fn test_release_wasm_does_not_export_test_seed() {
+    let exports = wasm_exports(pool::WASM);
+    assert!(exports.contains(&"execute_draw"));
+    assert!(!exports.contains(&"set_test_seed"));
+}
+
+#[test]
+fn test_deployed_pool_has_no_test_seed() {
+    let (env, factory_id, admin, token_id) = setup();
+    let pool = deploy(&env, &factory_id, &admin, &token_id, 7);
+    let args: Vec<Val> = soroban_sdk::vec![&env, None::<BytesN<32>>.into_val(&env)];
+    let res = env.try_invoke_contract::<(), soroban_sdk::Error>(&pool, &Symbol::new(&env, "set_test_seed"), args);
+    assert!(res.is_err());
+}
+
+#[test]
+#[should_panic(expected = "pool for this period already deployed")]
+fn test_deploy_pool_rejects_duplicate_period() {
+    let (env, factory_id, admin, token_id) = setup();
+    deploy(&env, &factory_id, &admin, &token_id, 7);
+
+    let factory = LuckyStakeFactoryClient::new(&env, &factory_id);
+    factory.deploy_pool(
+        &BytesN::from_array(&env, &[99u8; 32]),
+        &InitConfig {
+            admin,
+            token: token_id,
+            period_days: 7,
+        },
+    );
+}
+
+#[test]
+#[should_panic(expected = "already initialised")]
+fn test_initialize_twice_panics() {
+    let (env, factory_id, admin, _) = setup();
+    LuckyStakeFactoryClient::new(&env, &factory_id)
+        .initialize(&admin, &BytesN::from_array(&env, &[0u8; 32]));
+}
@@ -0,0 +1,43 @@
+#![no_std]
+
+//! LuckyStake Interface
+//!
+//! What other contracts need to recognise a LuckyStake pool, shared by the pool and the
+//! factory so both compute the same thing. A pool's fingerprint is
+//! `sha256(POOL_MAGIC || token || period_days)`, where `token` is the XDR encoding of
+//! the address as an `ScVal` and `period_days` is 4 bytes big-endian. It depends only on
+//! configuration, so a caller that knows which pool it expects (e.g. from the factory
+//! registry) can check a live contract against it with [`is_pool`].
+
+use soroban_sdk::{contractclient, xdr::ToXdr, Address, Bytes, BytesN, Env};
+
+/// Domain separator for pool fingerprints; bumped if the preimage ever changes.
+pub const POOL_MAGIC: &[u8] = b"LuckyStake/pool/v1";
+
+#[contractclient(name = "PoolIdentityClient")]
+pub trait PoolIdentity {
+    /// The pool's fingerprint, as computed by [`pool_fingerprint`].
+    fn get_pool_fingerprint(env: Env) -> BytesN<32>;
+}
+
+/// Fingerprint of a pool holding `token` with `period_days` days per period.
+pub fn pool_fingerprint(env: &Env, token: &Address, period_days: u32) -> BytesN<32> {
+    let mut data = Bytes::from_slice(env, POOL_MAGIC);
+    data.append(&token.clone().to_xdr(env));
+    data.extend_from_array(&period_days.to_be_bytes());
+    env.crypto().sha256(&data).into()
+}
+
+/// Whether `pool` answers `get_pool_fingerprint` with `expected`. Contracts that are not
+/// pools, or fail the call, are not.
+pub fn is_pool(env: &Env, pool: &Address, expected: &BytesN<32>) -> bool {
+    matches!(
+        PoolIdentityClient::new(env, pool).try_get_pool_fingerprint(),
+        Ok(Ok(fingerprint)) if fingerprint == *expected
+    )
+}
+
+/// Panics unless `pool` is a pool with the `expected` fingerprint.
+pub fn assert_is_pool(env: &Env, pool: &Address, expected: &BytesN<32>) {
+    assert!(is_pool(env, pool, expected), "not a LuckyStake pool");
+}
@@ -0,0 +1,90 @@
+//! Typed interface to a Blend lending pool.
+//!
+//! Every call into Blend goes through [`BlendClient`], generated from
+//! [`BlendPoolInterface`]. The test mock implements the same trait, so a signature that
+//! drifts from what the pool calls fails to compile instead of failing at invocation.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env, Map, Vec};
+
+/// Blend request types. Each supply type is withdrawn with the type right after it.
+pub const BLEND_SUPPLY: u32 = 0;
+pub const BLEND_WITHDRAW: u32 = 1;
+pub const BLEND_SUPPLY_COLLATERAL: u32 = 2;
+pub const BLEND_WITHDRAW_COLLATERAL: u32 = 3;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlendRequest {
+    pub request_type: u32,
+    pub address: Address,
+    pub amount: i128,
+}
+
+/// Blend `Positions` as returned by `get_positions`, keyed by reserve index.
+/// The pool only ever supplies its own token, so every supply/collateral entry is ours.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Positions {
+    pub liabilities: Map<u32, i128>,
+    pub collateral: Map<u32, i128>,
+    pub supply: Map<u32, i128>,
+}
+
+impl Positions {
+    /// Value of the supply and collateral positions together.
+    pub fn total_supplied(&self) -> i128 {
+        self.supply.values().iter().chain(self.collateral.values().iter()).sum()
+    }
+}
+
+/// Blend `PoolConfig` as returned by `get_config`. Only `status` is used: 0 (admin
+/// active) and 1 (active) accept supply; on ice, frozen and setup statuses do not.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolConfig {
+    pub oracle: Address,
+    pub bstop_rate: u32,
+    pub status: u32,
+    pub max_positions: u32,
+}
+
+/// Highest Blend pool status that still accepts supply.
+pub const BLEND_STATUS_ACTIVE: u32 = 1;
+
+/// The subset of the Blend pool contract the pool calls.
+#[contractclient(name = "BlendClient")]
+pub trait BlendPoolInterface {
+    /// Apply `requests` to `from`'s positions, pulling supplied tokens from `spender`
+    /// and sending withdrawn tokens to `to`. Returns `from`'s updated positions.
+    fn submit(env: Env, from: Address, spender: Address, to: Address, requests: Vec<BlendRequest>) -> Positions;
+
+    /// As `submit`, but supplied tokens are pulled through an allowance `spender`
+    /// granted the Blend pool instead of a `transfer` authorized by `spender`.
+    fn submit_with_allowance(
+        env: Env,
+        from: Address,
+        spender: Address,
+        to: Address,
+        requests: Vec<BlendRequest>,
+    ) -> Positions;
+
+    fn get_positions(env: Env, address: Address) -> Positions;
+
+    fn get_config(env: Env) -> PoolConfig;
+
+    /// Claim `from`'s emissions for the given reserve token ids, paid to `to`.
+    /// Returns the amount claimed.
+    fn claim(env: Env, from: Address, reserve_token_ids: Vec<u32>, to: Address) -> i128;
+}
+
+/// A single-request batch, the only shape the pool submits.
+pub fn single_request(env: &Env, request_type: u32, token: &Address, amount: i128) -> Vec<BlendRequest> {
+    soroban_sdk::vec![
+        env,
+        BlendRequest {
+            request_type,
+            address: token.clone(),
+            amount,
+        }
+    ]
+}
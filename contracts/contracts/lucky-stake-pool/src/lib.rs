@@ -12,34 +12,238 @@
 //! Blend integration: pool can supply token to a Blend lending pool to earn yield.
 //! Admin sets Blend pool address, then can call supply_to_blend / withdraw_from_blend / harvest_yield.
 //! SuppliedToBlend = principal supplied (excludes accrued interest). Actual balance from Blend get_positions.
+//! Several Blend pools can be listed with target weights (add_blend_pool / rebalance);
+//! SuppliedTo(pool) tracks principal per pool and SuppliedToBlend is their sum. The primary
+//! pool (BlendPool) receives deposits and is the target of the single-pool admin calls.
+//! Pool shares: the contract is also a SEP-41 token whose balances are the nominal deposit
+//! balances. Transferring shares moves tickets along with them.
+//! If Blend loses principal, recognize_loss lowers a global haircut factor: balances stay
+//! nominal (tickets included) and withdrawals pay the realizable share.
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Env, IntoVal, Symbol, Val, Vec,
+    contract, contracterror, contractimpl, contracttype, panic_with_error, symbol_short, token,
+    Address, BytesN, Env, Map, String, Symbol, Vec,
 };
 
+mod blend;
+mod selection;
+#[cfg(any(test, feature = "deterministic-draws"))]
+mod testing;
+
+pub use blend::{
+    BlendClient, BlendPoolInterface, BlendRequest, PoolConfig, Positions, BLEND_STATUS_ACTIVE, BLEND_SUPPLY,
+    BLEND_SUPPLY_COLLATERAL, BLEND_WITHDRAW, BLEND_WITHDRAW_COLLATERAL,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// Idle token balance cannot cover the prize without touching user principal.
+    /// Withdraw from Blend first (or enable auto-harvest).
+    InsufficientLiquidityForPrize = 1,
+    /// The minimum interval since the previous draw has not elapsed yet.
+    DrawTooEarly = 2,
+    /// A state-mutating entrypoint was re-entered while another one was in flight.
+    ReentrancyDetected = 3,
+    /// The pool has not been initialized yet.
+    NotInitialized = 4,
+    /// `harvest` was called before the admin configured a harvest policy.
+    HarvestPolicyNotSet = 5,
+    /// The policy's minimum interval since the previous harvest has not elapsed.
+    HarvestTooEarly = 6,
+    /// No Blend pool has been configured.
+    BlendPoolNotSet = 7,
+    /// The Blend pool's status is not active (on ice, frozen or still in setup).
+    /// Read the status code with `get_blend_pool_status`.
+    BlendPoolUnhealthy = 8,
+    /// Too many depositors for a linear draw; finish `rebuild_index` first.
+    DrawIndexRequired = 9,
+}
+
 #[contracttype]
 pub enum DataKey {
     Admin,
     Token,
     PeriodDays,
+    /// A user's nominal share balance (persistent, like every per-user key).
     Balance(Address),
+    /// A user's balance tickets (persistent).
     Tickets(Address),
     TotalDeposits,
     TotalTickets,
     PrizeFund,
+    /// Everyone holding a balance or tickets, in the order they joined (persistent, so
+    /// calls that do not walk it never load it).
     Depositors,
     DrawNonce,
     BlendPool,
     SuppliedToBlend,
     ReceiptToken,
     AdminYield,
+    AutoHarvest,
+    MinParticipants,
+    DrawResult(u64),
+    LastDrawTime,
+    MinDrawInterval,
+    DrawOverride,
+    Locked,
+    HarvestPolicy,
+    LastHarvestTime,
+    BlendRequestType,
+    WithdrawalQueue,
+    /// A user's amount in the withdrawal queue (persistent).
+    QueuedAmount(Address),
+    QueuedTotal,
+    /// A user's processed withdrawals awaiting `claim_withdrawal` (persistent).
+    ClaimableWithdrawal(Address),
+    ReservedForClaims,
+    HaircutFactor,
+    BlendPools,
+    SuppliedTo(Address),
+    ForceBlendSupply,
+    YieldSplitBps,
+    YieldIndex,
+    /// `YieldIndex` as of the user's last settlement (persistent).
+    UserYieldIndex(Address),
+    /// SEP-41 share allowance (temporary, see `write_allowance`).
+    Allowance(Address, Address),
+}
+
+/// Storage keys added after `DataKey` reached the 50-variant limit of `contracttype` enums.
+#[contracttype]
+pub enum DataKeyExt {
+    /// Bumped whenever a draw weight may have changed; see `DrawIndex`.
+    TicketsVersion,
+    DrawIndex,
+    /// Cumulative `(participant, tickets)` entries of one `DrawIndex` chunk (persistent).
+    IndexChunk(u32),
+}
+
+/// Storage keys added after `DataKeyExt` filled up as well. Variant names must stay
+/// distinct from the other key enums': a key encodes as its variant name only.
+#[contracttype]
+pub enum DataKeyExt2 {
+    SnapshotOffset,
+    /// A user's `TicketCheckpoint`, while their latest ticket change is after the
+    /// snapshot time (persistent).
+    TicketCheckpoint(Address),
+    /// Depositor yield counted in `TotalDeposits` but not yet settled into any
+    /// `Balance`, rounding dust included. `credit_yield` spreads over the rest.
+    UnsettledYield,
+}
+
+/// Why a draw rolled its prize over to the next period instead of paying out.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RolloverReason {
+    NotEnoughParticipants,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DrawStatus {
+    Paid,
+    RolledOver(RolloverReason),
+}
+
+/// Cumulative ticket index over the depositor list, built in batches by `rebuild_index`.
+/// Participants are stored `DRAW_INDEX_CHUNK` at a time with running ticket totals;
+/// `chunk_ends[i]` is the running total at the end of chunk `i`. The index is ready once
+/// `next` reaches the end of the depositor list and only while `version` still matches
+/// `DataKeyExt::TicketsVersion`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawIndex {
+    pub version: u64,
+    /// Position in the depositor list the next batch starts at.
+    pub next: u32,
+    pub participants: u32,
+    pub total: i128,
+    pub chunk_ends: Vec<i128>,
+}
+
+/// Outcome of a single draw, persisted under `DataKey::DrawResult(nonce)`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawResult {
+    pub nonce: u64,
+    pub winner: Option<Address>,
+    pub prize: i128,
+    pub participants: u32,
+    pub timestamp: u64,
+    pub status: DrawStatus,
+}
+
+/// Most depositors `execute_draw` scans one by one; larger pools draw through the index
+/// built by `rebuild_index`. The scan reads about six persistent entries per depositor,
+/// which keeps it small.
+pub const MAX_LINEAR_DRAW_DEPOSITORS: u32 = 12;
+
+/// Participants per `DataKeyExt::IndexChunk`.
+pub const DRAW_INDEX_CHUNK: u32 = 64;
+
+/// Fixed-point scale of the haircut factor: `HAIRCUT_SCALE` means no loss.
+pub const HAIRCUT_SCALE: i128 = 1_000_000_000;
+
+/// Fixed-point scale of the depositor yield index (nominal yield per unit of balance).
+pub const YIELD_INDEX_SCALE: i128 = 1_000_000_000_000;
+
+/// A Blend pool in the allocation list and its target share of supplied principal.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlendAllocation {
+    pub pool: Address,
+    pub weight_bps: u32,
+}
+
+/// `rebalance` leaves a pool alone while it is within this many bps of the total
+/// supplied principal from its target.
+pub const REBALANCE_TOLERANCE_BPS: i128 = 100;
+
+/// Payload of every `("blend", <op>)` event. `supplied` is `SuppliedToBlend` after the op.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlendEvent {
+    pub pool: Address,
+    pub requested: i128,
+    pub received: i128,
+    pub min_return: i128,
+    pub supplied: i128,
+}
+
+/// A withdrawal waiting in the FIFO queue for Blend liquidity to return.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueuedWithdrawal {
+    pub user: Address,
+    pub amount: i128,
 }
 
+/// SEP-41 allowance of pool shares granted by `approve`.
 #[contracttype]
-pub struct BlendRequest {
-    pub request_type: u32,
-    pub address: Address,
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowanceValue {
     pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+/// Limits for the permissionless `harvest` entrypoint.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HarvestPolicy {
+    pub max_per_call: i128,
+    pub min_interval_secs: u64,
+    pub caller_reward_bps: u32,
+}
+
+/// A user's `Tickets` changed at `changed_at`, after the snapshot time. `counted` is the
+/// least they held from the snapshot time on, which is what the draw counts.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TicketCheckpoint {
+    pub counted: i128,
+    pub changed_at: u64,
 }
 
 #[contract]
@@ -48,6 +252,7 @@ pub struct LuckyStakePool;
 #[contractimpl]
 impl LuckyStakePool {
     pub fn initialize(env: Env, admin: Address, token: Address, period_days: u32) {
+        Self::assert_unlocked(&env);
         if env.storage().instance().has(&DataKey::Admin) {
             panic!("already initialised");
         }
@@ -67,154 +272,194 @@ impl LuckyStakePool {
         env.storage().instance().set(&DataKey::DrawNonce, &0u64);
 
         let empty: Vec<Address> = Vec::new(&env);
-        env.storage().instance().set(&DataKey::Depositors, &empty);
+        env.storage().persistent().set(&DataKey::Depositors, &empty);
     }
 
     pub fn deposit(env: Env, depositor: Address, amount: i128) {
+        Self::require_initialized(&env);
         depositor.require_auth();
-        assert!(amount > 0, "deposit amount must be greater than zero");
-
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
-        let token_client = token::Client::new(&env, &token_id);
-        let self_addr = env.current_contract_address();
-
-        // 1. Transfer from User to Contract
-        token_client.transfer(&depositor, &self_addr, &amount);
+        Self::lock(&env);
+        Self::deposit_inner(&env, &depositor, amount);
+        Self::unlock(&env);
+    }
 
-        // 2. Automatically Supply to Blend (if pool is configured)
-        if let Some(blend_pool) = env.storage().instance().get::<_, Address>(&DataKey::BlendPool) {
-            let expiration = env.ledger().sequence() + 50_000;
-            token_client.approve(&self_addr, &blend_pool, &amount, &expiration);
+    /// Burn `amount` of nominal balance and pay out its realizable value, which is less
+    /// than `amount` once a Blend loss has been recognized.
+    pub fn withdraw(env: Env, depositor: Address, amount: i128) {
+        Self::require_initialized(&env);
+        depositor.require_auth();
+        Self::lock(&env);
+        assert!(amount > 0, "withdraw amount must be greater than zero");
 
-            let request = BlendRequest {
-                request_type: 0u32, // Supply
-                address: token_id.clone(),
-                amount,
-            };
-            let mut requests: Vec<BlendRequest> = Vec::new(&env);
-            requests.push_back(request);
+        Self::settle_yield(&env, &depositor);
+        Self::debit_position(&env, &depositor, amount);
 
-            let args = soroban_sdk::vec![
-                &env,
-                self_addr.clone().into_val(&env),
-                self_addr.clone().into_val(&env),
-                self_addr.clone().into_val(&env),
-                requests.into_val(&env),
-            ];
+        let payout = Self::realizable(&env, amount);
+        if payout > 0 {
+            let token_id = Self::token_id(&env);
+            token::Client::new(&env, &token_id).transfer(
+                &env.current_contract_address(),
+                &depositor,
+                &payout,
+            );
+        }
 
-            // Atomic invocation to Blend
-            env.invoke_contract::<Val>(&blend_pool, &Symbol::new(&env, "submit_with_allowance"), args);
+        Self::unlock(&env);
+    }
 
-            // Emit a "Token Receipt" event for the user to verify on the explorer
-            env.events().publish(
-                (Symbol::new(&env, "LUCKY_STAKED"), depositor.clone()),
-                amount
-            );
+    /// Exit without waiting for liquidity: the balance and tickets are burned right away
+    /// and the amount joins the withdrawal queue. `process_queue` matures it once Blend
+    /// pays out, after which the user collects it with `claim_queued`.
+    pub fn request_withdrawal(env: Env, user: Address, amount: i128) {
+        Self::require_initialized(&env);
+        user.require_auth();
+        Self::lock(&env);
+        assert!(amount > 0, "withdraw amount must be greater than zero");
 
-            // 3. Mint Receipt Tokens to User
-            if let Some(receipt_token_id) = env.storage().instance().get::<_, Address>(&DataKey::ReceiptToken) {
-                 let receipt_client = token::Client::new(&env, &receipt_token_id);
-                 // Note: Pool contract must be the admin of the Receipt Token to mint
-                 receipt_client.transfer(&self_addr, &depositor, &amount); 
-            }
+        Self::settle_yield(&env, &user);
+        Self::debit_position(&env, &user, amount);
+        let amount = Self::realizable(&env, amount);
 
-            // Update internal tracking
-            let supplied: i128 = env.storage().instance().get(&DataKey::SuppliedToBlend).unwrap_or(0);
-            env.storage().instance().set(&DataKey::SuppliedToBlend, &(supplied + amount));
-        }
+        let mut queue: Vec<QueuedWithdrawal> = env
+            .storage()
+            .instance()
+            .get(&DataKey::WithdrawalQueue)
+            .unwrap_or_else(|| Vec::new(&env));
+        queue.push_back(QueuedWithdrawal {
+            user: user.clone(),
+            amount,
+        });
+        env.storage().instance().set(&DataKey::WithdrawalQueue, &queue);
 
-        // 4. Update User State (Tickets and Balance)
-        let period_days: u32 = env.storage().instance().get(&DataKey::PeriodDays).unwrap();
-        let tickets_to_add = amount * (period_days as i128);
+        let queued: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::QueuedAmount(user.clone()))
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&DataKey::QueuedAmount(user.clone()), &(queued + amount));
+        let queued_total: i128 = env.storage().instance().get(&DataKey::QueuedTotal).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::QueuedTotal, &(queued_total + amount));
 
-        let current_balance: i128 = env.storage().instance().get(&DataKey::Balance(depositor.clone())).unwrap_or(0);
-        let current_tickets: i128 = env.storage().instance().get(&DataKey::Tickets(depositor.clone())).unwrap_or(0);
+        Self::unlock(&env);
+    }
 
-        env.storage().instance().set(&DataKey::Balance(depositor.clone()), &(current_balance + amount));
-        env.storage().instance().set(&DataKey::Tickets(depositor.clone()), &(current_tickets + tickets_to_add));
+    /// Pull liquidity back from Blend for up to `max_items` queued withdrawals and mature
+    /// them in FIFO order. The head of the queue may be matured partially. Callable by
+    /// anyone; returns the number of queue entries fully matured.
+    pub fn process_queue(env: Env, max_items: u32) -> u32 {
+        Self::require_initialized(&env);
+        Self::lock(&env);
 
-        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap();
-        let total_tickets: i128 = env.storage().instance().get(&DataKey::TotalTickets).unwrap();
+        let mut queue: Vec<QueuedWithdrawal> = env
+            .storage()
+            .instance()
+            .get(&DataKey::WithdrawalQueue)
+            .unwrap_or_else(|| Vec::new(&env));
 
-        env.storage().instance().set(&DataKey::TotalDeposits, &(total + amount));
-        env.storage().instance().set(&DataKey::TotalTickets, &(total_tickets + tickets_to_add));
+        let mut wanted: i128 = 0;
+        for item in queue.iter().take(max_items as usize) {
+            wanted += item.amount;
+        }
 
-        // Add to depositors list
-        let mut depositors: Vec<Address> = env.storage().instance().get(&DataKey::Depositors).unwrap_or_else(|| Vec::new(&env));
-        let mut found = false;
-        for d in depositors.iter() {
-            if d == depositor { found = true; break; }
+        let free = Self::free_idle(&env);
+        if wanted > free {
+            if let Some(blend_pool) = env.storage().instance().get::<_, Address>(&DataKey::BlendPool) {
+                let token_id = Self::token_id(&env);
+                let requested = wanted - free;
+                let received = Self::withdraw_from_blend_pool(&env, &blend_pool, &token_id, requested);
+                Self::add_supplied(&env, &blend_pool, -received);
+                Self::emit_blend_event(&env, symbol_short!("withdraw"), &blend_pool, requested, received, 0);
+            }
         }
-        if !found {
-            depositors.push_back(depositor.clone());
-            env.storage().instance().set(&DataKey::Depositors, &depositors);
+
+        let mut free = Self::free_idle(&env);
+        let mut matured_items: u32 = 0;
+        while matured_items < max_items && free > 0 {
+            let Some(mut head) = queue.first() else {
+                break;
+            };
+            let matured = head.amount.min(free);
+            Self::mature_claim(&env, &head.user, matured);
+            free -= matured;
+
+            if matured == head.amount {
+                queue.pop_front();
+                matured_items += 1;
+            } else {
+                head.amount -= matured;
+                queue.set(0, head);
+                break;
+            }
         }
-    }
+        env.storage().instance().set(&DataKey::WithdrawalQueue, &queue);
 
-    pub fn withdraw(env: Env, depositor: Address, amount: i128) {
-        depositor.require_auth();
-        assert!(amount > 0, "withdraw amount must be greater than zero");
+        Self::unlock(&env);
+        matured_items
+    }
 
-        let balance: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Balance(depositor.clone()))
-            .unwrap_or(0);
-        assert!(balance >= amount, "insufficient balance");
+    /// Pay out everything that `process_queue` has matured for `user`.
+    pub fn claim_queued(env: Env, user: Address) -> i128 {
+        Self::require_initialized(&env);
+        user.require_auth();
+        Self::lock(&env);
 
-        let tickets: i128 = env
+        let amount: i128 = env
             .storage()
-            .instance()
-            .get(&DataKey::Tickets(depositor.clone()))
+            .persistent()
+            .get(&DataKey::ClaimableWithdrawal(user.clone()))
             .unwrap_or(0);
-
-        let tickets_to_remove = if balance > 0 {
-            (tickets * amount) / balance
-        } else {
-            0i128
-        };
-
-        let new_balance = balance - amount;
-        let new_tickets = tickets - tickets_to_remove;
+        assert!(amount > 0, "nothing to claim");
 
         env.storage()
-            .instance()
-            .set(&DataKey::Balance(depositor.clone()), &new_balance);
+            .persistent()
+            .remove(&DataKey::ClaimableWithdrawal(user.clone()));
+        let reserved: i128 = env.storage().instance().get(&DataKey::ReservedForClaims).unwrap_or(0);
         env.storage()
             .instance()
-            .set(&DataKey::Tickets(depositor.clone()), &new_tickets);
+            .set(&DataKey::ReservedForClaims, &(reserved - amount));
 
-        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap();
-        let total_tickets: i128 = env.storage().instance().get(&DataKey::TotalTickets).unwrap();
+        let token_id = Self::token_id(&env);
+        token::Client::new(&env, &token_id).transfer(&env.current_contract_address(), &user, &amount);
 
+        Self::unlock(&env);
+        amount
+    }
+
+    pub fn get_queue_length(env: Env) -> u32 {
         env.storage()
             .instance()
-            .set(&DataKey::TotalDeposits, &(total - amount));
-        env.storage()
-            .instance()
-            .set(&DataKey::TotalTickets, &(total_tickets - tickets_to_remove));
+            .get::<_, Vec<QueuedWithdrawal>>(&DataKey::WithdrawalQueue)
+            .map(|q| q.len())
+            .unwrap_or(0)
+    }
 
-        // 2. Burn Receipt Tokens from User
-        if let Some(receipt_token_id) = env.storage().instance().get::<_, Address>(&DataKey::ReceiptToken) {
-            let receipt_client = token::Client::new(&env, &receipt_token_id);
-            // Move tokens back to pool (or burn if the token supports it)
-            receipt_client.transfer(&depositor, &env.current_contract_address(), &amount);
-        }
+    /// Amount `user` still has waiting in the queue (not yet matured).
+    pub fn get_queued_amount(env: Env, user: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::QueuedAmount(user))
+            .unwrap_or(0)
+    }
 
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
-        token::Client::new(&env, &token_id).transfer(
-            &env.current_contract_address(),
-            &depositor,
-            &amount,
-        );
+    /// Matured amount `user` can collect with `claim_queued`.
+    pub fn get_claimable_withdrawal(env: Env, user: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ClaimableWithdrawal(user))
+            .unwrap_or(0)
     }
 
     pub fn add_prize(env: Env, amount: i128) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::lock(&env);
         assert!(amount > 0, "prize amount must be greater than zero");
 
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        let token_id = Self::token_id(&env);
         token::Client::new(&env, &token_id).transfer(
             &admin,
             &env.current_contract_address(),
@@ -225,11 +470,20 @@ impl LuckyStakePool {
         env.storage()
             .instance()
             .set(&DataKey::PrizeFund, &(current + amount));
+
+        Self::unlock(&env);
     }
 
-    pub fn execute_draw(env: Env) -> Address {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+    /// Pick a winner weighted by tickets and pay out the prize fund.
+    /// Returns `None` when the draw rolled over (prize retained, nonce still advances).
+    pub fn execute_draw(env: Env) -> Option<Address> {
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::lock(&env);
+        // Read before the draw stamps its own time as the last draw time
+        let snapshot = Self::snapshot_time(&env);
+
+        Self::enforce_draw_interval(&env);
 
         let prize: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap_or(0);
         assert!(prize > 0, "no prize to distribute");
@@ -243,43 +497,70 @@ impl LuckyStakePool {
 
         let depositors: Vec<Address> = env
             .storage()
-            .instance()
+            .persistent()
             .get(&DataKey::Depositors)
             .unwrap_or_else(|| Vec::new(&env));
 
-        let mut participants: Vec<(Address, i128)> = Vec::new(&env);
-        let mut acc: i128 = 0;
-        for d in depositors.iter() {
-            let t: i128 = env
-                .storage()
-                .instance()
-                .get(&DataKey::Tickets(d.clone()))
-                .unwrap_or(0);
-            if t > 0 {
-                acc += t;
-                participants.push_back((d.clone(), t));
+        // A complete, current index replaces the per-depositor scan; without one the
+        // scan is only allowed up to MAX_LINEAR_DRAW_DEPOSITORS
+        let index = Self::ready_index(&env, depositors.len());
+        let (participants, participant_count, acc) = match &index {
+            Some(index) => (None, index.participants, index.total),
+            None => {
+                if depositors.len() > MAX_LINEAR_DRAW_DEPOSITORS {
+                    panic_with_error!(&env, Error::DrawIndexRequired);
+                }
+                let (participants, acc) = Self::scan_participants(&env, &depositors, snapshot);
+                let count = participants.len();
+                (Some(participants), count, acc)
             }
-        }
-        assert!(acc > 0, "none");
+        };
+        assert!(acc > 0, "no tickets in pool");
 
-        let random: u64 = env.prng().gen();
-        let winning_ticket_index = (random as i128) % acc;
+        let nonce: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::DrawNonce)
+            .unwrap_or(0);
+        env.storage().instance().set(&DataKey::DrawNonce, &(nonce + 1));
+        // The epoch changed: tickets gained after the snapshot count from now on
+        Self::tickets_changed(&env);
 
-        let mut cumulative: i128 = 0;
-        let mut winner = participants.get(0).unwrap().0.clone();
-        for p in participants.iter() {
-            cumulative += p.1;
-            if winning_ticket_index < cumulative {
-                winner = p.0.clone();
-                break;
-            }
+        let min_participants: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::MinParticipants)
+            .unwrap_or(1);
+        if participant_count < min_participants {
+            Self::record_draw(
+                &env,
+                DrawResult {
+                    nonce,
+                    winner: None,
+                    prize,
+                    participants: participant_count,
+                    timestamp: env.ledger().timestamp(),
+                    status: DrawStatus::RolledOver(RolloverReason::NotEnoughParticipants),
+                },
+            );
+            Self::unlock(&env);
+            return None;
         }
 
+        Self::ensure_prize_liquidity(&env, prize);
+
+        let random = Self::draw_random(&env, nonce);
+        let winning_ticket_index = match &participants {
+            Some(participants) => selection::select_winner(participants, random as u128).1,
+            None => (random as i128) % acc,
+        };
+        let winner = Self::ticket_owner(&env, index.as_ref(), participants.as_ref(), winning_ticket_index);
+
         // 85% of prize to winner, 15% retained as admin yield
         let admin_share: i128 = (prize * 15) / 100;
         let winner_amount: i128 = prize - admin_share;
 
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        let token_id = Self::token_id(&env);
         token::Client::new(&env, &token_id).transfer(
             &env.current_contract_address(),
             &winner,
@@ -300,19 +581,178 @@ impl LuckyStakePool {
 
         env.storage().instance().set(&DataKey::PrizeFund, &0i128);
 
-        let nonce: u64 = env
+        Self::record_draw(
+            &env,
+            DrawResult {
+                nonce,
+                winner: Some(winner.clone()),
+                prize,
+                participants: participant_count,
+                timestamp: env.ledger().timestamp(),
+                status: DrawStatus::Paid,
+            },
+        );
+
+        Self::unlock(&env);
+        Some(winner)
+    }
+
+    /// Add depositors `[start, start + batch)` to the draw index; `start` must be `0`
+    /// (restart) or where the previous batch stopped. Anyone can call it: the index only
+    /// mirrors stored tickets, and any change to a draw weight makes it stale until
+    /// rebuilt. Built after the snapshot time, it survives later deposits, which only add
+    /// pending tickets.
+    /// Returns the position the next batch starts at.
+    pub fn rebuild_index(env: Env, start: u32, batch: u32) -> u32 {
+        Self::require_initialized(&env);
+        Self::lock(&env);
+        let version: u64 = env.storage().instance().get(&DataKeyExt::TicketsVersion).unwrap_or(0);
+        let mut index = if start == 0 {
+            DrawIndex {
+                version,
+                next: 0,
+                participants: 0,
+                total: 0,
+                chunk_ends: Vec::new(&env),
+            }
+        } else {
+            let index: DrawIndex = env
+                .storage()
+                .instance()
+                .get(&DataKeyExt::DrawIndex)
+                .expect("no index build in progress");
+            assert!(index.version == version, "index is stale, restart from 0");
+            assert!(index.next == start, "batch must start where the last one ended");
+            index
+        };
+
+        let depositors: Vec<Address> = env
             .storage()
-            .instance()
-            .get(&DataKey::DrawNonce)
-            .unwrap_or(0);
-        env.storage().instance().set(&DataKey::DrawNonce, &(nonce + 1));
+            .persistent()
+            .get(&DataKey::Depositors)
+            .unwrap_or_else(|| Vec::new(&env));
+        let end = start.saturating_add(batch).min(depositors.len());
+        let mut chunk_id = index.participants / DRAW_INDEX_CHUNK;
+        let mut chunk: Vec<(Address, i128)> = if index.participants % DRAW_INDEX_CHUNK == 0 {
+            Vec::new(&env)
+        } else {
+            env.storage().persistent().get(&DataKeyExt::IndexChunk(chunk_id)).unwrap()
+        };
+        for i in start..end {
+            let d = depositors.get(i).unwrap();
+            let tickets = Self::draw_tickets(&env, &d);
+            if tickets <= 0 {
+                continue;
+            }
+            index.total += tickets;
+            chunk.push_back((d, index.total));
+            index.participants += 1;
+            if chunk.len() == DRAW_INDEX_CHUNK {
+                Self::store_index_chunk(&env, &mut index, chunk_id, &chunk);
+                chunk_id += 1;
+                chunk = Vec::new(&env);
+            }
+        }
+        if !chunk.is_empty() {
+            Self::store_index_chunk(&env, &mut index, chunk_id, &chunk);
+        }
+        index.next = end;
+        env.storage().instance().set(&DataKeyExt::DrawIndex, &index);
+
+        env.events().publish(
+            (symbol_short!("index"), symbol_short!("progress")),
+            (end, depositors.len(), index.total),
+        );
+        Self::unlock(&env);
+        end
+    }
+
+    /// Whether `execute_draw` would pick the winner through the index.
+    pub fn is_index_ready(env: Env) -> bool {
+        let depositors = Self::get_depositor_count(env.clone());
+        Self::ready_index(&env, depositors).is_some()
+    }
+
+    /// Minimum number of participants with tickets for a draw to pay out.
+    /// Below it, the draw rolls over. Defaults to 1.
+    pub fn set_min_participants(env: Env, n: u32) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        assert!(n > 0, "min participants must be at least 1");
+        env.storage().instance().set(&DataKey::MinParticipants, &n);
+    }
+
+    pub fn get_min_participants(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::MinParticipants).unwrap_or(1)
+    }
+
+    pub fn get_draw_result(env: Env, nonce: u64) -> Option<DrawResult> {
+        env.storage().persistent().get(&DataKey::DrawResult(nonce))
+    }
+
+    /// Minimum seconds between two draws. Defaults to `period_days * 86400`.
+    pub fn set_min_draw_interval(env: Env, seconds: u64) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        env.storage().instance().set(&DataKey::MinDrawInterval, &seconds);
+    }
+
+    pub fn get_min_draw_interval(env: Env) -> u64 {
+        Self::min_draw_interval(&env)
+    }
+
+    /// Fix each draw's tickets `seconds` before the next draw time: ticket increases
+    /// after that count from the following draw, decreases count at once. Draws before
+    /// the first one have no draw time to count back from and use current tickets. `0`
+    /// turns it off.
+    pub fn set_snapshot_offset(env: Env, seconds: u64) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        if seconds == 0 {
+            env.storage().instance().remove(&DataKeyExt2::SnapshotOffset);
+        } else {
+            assert!(
+                seconds < Self::min_draw_interval(&env),
+                "snapshot offset must be shorter than the draw interval"
+            );
+            env.storage().instance().set(&DataKeyExt2::SnapshotOffset, &seconds);
+        }
+        Self::tickets_changed(&env);
+    }
+
+    pub fn get_snapshot_offset(env: Env) -> u64 {
+        env.storage().instance().get(&DataKeyExt2::SnapshotOffset).unwrap_or(0)
+    }
+
+    /// When the next draw's tickets are fixed, if it has a snapshot time.
+    pub fn get_snapshot_time(env: Env) -> Option<u64> {
+        Self::snapshot_time(&env)
+    }
+
+    /// Emergency switch letting the next draw skip the interval check.
+    /// The flag is consumed by that draw.
+    pub fn set_draw_override(env: Env, enabled: bool) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        env.storage().instance().set(&DataKey::DrawOverride, &enabled);
+    }
+
+    pub fn get_draw_override(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::DrawOverride).unwrap_or(false)
+    }
 
-        winner
+    pub fn get_last_draw_time(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::LastDrawTime)
     }
 
     pub fn withdraw_admin_yield(env: Env, to: Address, amount: i128) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::lock(&env);
         assert!(amount > 0, "amount must be positive");
 
         let current_admin_yield: i128 = env
@@ -322,7 +762,7 @@ impl LuckyStakePool {
             .unwrap_or(0);
         assert!(current_admin_yield >= amount, "insufficient admin yield");
 
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        let token_id = Self::token_id(&env);
         token::Client::new(&env, &token_id).transfer(
             &env.current_contract_address(),
             &to,
@@ -332,14 +772,45 @@ impl LuckyStakePool {
         env.storage()
             .instance()
             .set(&DataKey::AdminYield, &(current_admin_yield - amount));
+
+        Self::unlock(&env);
+    }
+
+    /// Length of the depositor list a linear draw scans. Past `MAX_LINEAR_DRAW_DEPOSITORS`
+    /// `execute_draw` fails with `DrawIndexRequired` until `rebuild_index` completes.
+    pub fn get_depositor_count(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get::<_, Vec<Address>>(&DataKey::Depositors)
+            .map(|d| d.len())
+            .unwrap_or(0)
+    }
+
+    /// Depositors the next draw would weigh: those with draw tickets, as
+    /// `DrawResult::participants` would count them. Read from the index when it is ready;
+    /// otherwise the list is scanned, which past `MAX_LINEAR_DRAW_DEPOSITORS` fails with
+    /// `DrawIndexRequired` like the draw.
+    pub fn get_participant_count(env: Env) -> u32 {
+        let depositors: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Depositors)
+            .unwrap_or_else(|| Vec::new(&env));
+        if let Some(index) = Self::ready_index(&env, depositors.len()) {
+            return index.participants;
+        }
+        if depositors.len() > MAX_LINEAR_DRAW_DEPOSITORS {
+            panic_with_error!(&env, Error::DrawIndexRequired);
+        }
+        Self::scan_participants(&env, &depositors, Self::snapshot_time(&env)).0.len()
     }
 
     pub fn get_balance(env: Env, user: Address) -> i128 {
-        env.storage().instance().get(&DataKey::Balance(user)).unwrap_or(0)
+        env.storage().persistent().get(&DataKey::Balance(user)).unwrap_or(0)
     }
 
     pub fn get_tickets(env: Env, user: Address) -> i128 {
-        env.storage().instance().get(&DataKey::Tickets(user)).unwrap_or(0)
+        env.storage().persistent().get(&DataKey::Tickets(user)).unwrap_or(0)
     }
 
     pub fn get_total_deposits(env: Env) -> i128 {
@@ -358,12 +829,21 @@ impl LuckyStakePool {
         env.storage().instance().get(&DataKey::PeriodDays).unwrap_or(0)
     }
 
+    pub fn is_initialized(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::Admin)
+    }
+
+    /// Identifies this pool to other contracts; see `lucky_stake_interface`.
+    pub fn get_pool_fingerprint(env: Env) -> BytesN<32> {
+        lucky_stake_interface::pool_fingerprint(&env, &Self::token_id(&env), Self::get_period_days(env.clone()))
+    }
+
     pub fn get_admin(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+        Self::admin(&env)
     }
 
     pub fn get_token(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Token).unwrap()
+        Self::token_id(&env)
     }
 
     pub fn get_draw_nonce(env: Env) -> u64 {
@@ -371,17 +851,33 @@ impl LuckyStakePool {
     }
 
     pub fn set_blend_pool(env: Env, blend_pool: Address) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::assert_unlocked(&env);
+        let previous: Option<Address> = env.storage().instance().get(&DataKey::BlendPool);
         env.storage().instance().set(&DataKey::BlendPool, &blend_pool);
         if !env.storage().instance().has(&DataKey::SuppliedToBlend) {
             env.storage().instance().set(&DataKey::SuppliedToBlend, &0i128);
         }
+        let mut pools = Self::blend_pools(&env);
+        if Self::pool_index(&pools, &blend_pool).is_none() {
+            let weight_bps = if pools.is_empty() { 10_000 } else { 0 };
+            pools.push_back(BlendAllocation {
+                pool: blend_pool.clone(),
+                weight_bps,
+            });
+            env.storage().instance().set(&DataKey::BlendPools, &pools);
+        }
+        env.events().publish(
+            (symbol_short!("blend"), symbol_short!("set_pool")),
+            (previous, blend_pool),
+        );
     }
 
     pub fn supply_to_blend(env: Env, amount: i128) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::lock(&env);
         assert!(amount > 0, "amt");
 
         let blend_pool: Address = env
@@ -389,33 +885,15 @@ impl LuckyStakePool {
             .instance()
             .get(&DataKey::BlendPool)
             .unwrap_or_else(|| panic!("blend"));
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        Self::assert_blend_healthy(&env, &blend_pool);
+        let token_id = Self::token_id(&env);
         let token_client = token::Client::new(&env, &token_id);
         let self_addr = env.current_contract_address();
 
         // Verification: Get balance before supply
         let balance_before = token_client.balance(&self_addr);
 
-        let expiration = env.ledger().sequence() + 50_000;
-        token_client.approve(&self_addr, &blend_pool, &amount, &expiration);
-
-        let request = BlendRequest {
-            request_type: 0u32,
-            address: token_id.clone(),
-            amount,
-        };
-        let mut requests: Vec<BlendRequest> = Vec::new(&env);
-        requests.push_back(request);
-
-        let args = soroban_sdk::vec![
-            &env,
-            self_addr.clone().into_val(&env),
-            self_addr.clone().into_val(&env),
-            self_addr.clone().into_val(&env),
-            requests.into_val(&env),
-        ];
-
-        env.invoke_contract::<Val>(&blend_pool, &Symbol::new(&env, "submit_with_allowance"), args);
+        Self::supply_to_blend_pool(&env, &blend_pool, &token_id, amount);
 
         // Verification: Confirm balance decreased by the full amount
         let balance_after = token_client.balance(&self_addr);
@@ -424,105 +902,324 @@ impl LuckyStakePool {
             "Verification failed: Funds did not leave the contract for Blend"
         );
 
-        let supplied: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::SuppliedToBlend)
-            .unwrap_or(0);
-        env.storage()
-            .instance()
-            .set(&DataKey::SuppliedToBlend, &(supplied + amount));
+        Self::add_supplied(&env, &blend_pool, amount);
+        Self::emit_blend_event(
+            &env,
+            symbol_short!("supply"),
+            &blend_pool,
+            amount,
+            balance_before - balance_after,
+            amount,
+        );
+
+        Self::unlock(&env);
     }
 
     pub fn withdraw_from_blend(env: Env, amount: i128, min_return: i128) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::lock(&env);
         assert!(amount > 0, "amt");
         assert!(min_return >= 0 && min_return <= amount, "min");
 
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
-        let self_addr = env.current_contract_address();
-        let balance_before = token::Client::new(&env, &token_id).balance(&self_addr);
-
+        let token_id = Self::token_id(&env);
         let blend_pool: Address = env
             .storage()
             .instance()
             .get(&DataKey::BlendPool)
             .unwrap_or_else(|| panic!("blend"));
 
-        let mut requests: Vec<BlendRequest> = Vec::new(&env);
-        requests.push_back(BlendRequest {
-            request_type: 1u32,
-            address: token_id.clone(),
-            amount,
-        });
-
-        let args = soroban_sdk::vec![
-            &env,
-            self_addr.clone().into_val(&env),
-            self_addr.clone().into_val(&env),
-            self_addr.clone().into_val(&env),
-            requests.into_val(&env),
-        ];
-
-        env.invoke_contract::<Val>(&blend_pool, &Symbol::new(&env, "submit"), args);
-
-        let balance_after = token::Client::new(&env, &token_id).balance(&self_addr);
-        let received = balance_after - balance_before;
+        let received = Self::withdraw_principal(&env, &blend_pool, &token_id, amount);
         assert!(received >= min_return, "slip");
+        Self::emit_blend_event(&env, symbol_short!("withdraw"), &blend_pool, amount, received, min_return);
 
-        let supplied: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::SuppliedToBlend)
-            .unwrap_or(0);
-        let new_supplied = supplied - amount;
-        env.storage()
-            .instance()
-            .set(&DataKey::SuppliedToBlend, &new_supplied);
+        Self::unlock(&env);
     }
 
     pub fn harvest_yield(env: Env, amount: i128, min_return: i128) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::lock(&env);
         assert!(amount > 0, "amt");
         assert!(min_return >= 0 && min_return <= amount, "min");
 
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
-        let self_addr = env.current_contract_address();
-        let balance_before = token::Client::new(&env, &token_id).balance(&self_addr);
-
+        let token_id = Self::token_id(&env);
         let blend_pool: Address = env
             .storage()
             .instance()
             .get(&DataKey::BlendPool)
             .unwrap_or_else(|| panic!("blend"));
 
-        let mut requests: Vec<BlendRequest> = Vec::new(&env);
-        requests.push_back(BlendRequest {
-            request_type: 1u32,
-            address: token_id.clone(),
-            amount,
-        });
+        let received = Self::withdraw_from_blend_pool(&env, &blend_pool, &token_id, amount);
+        assert!(received >= min_return, "slip");
 
-        let args = soroban_sdk::vec![
-            &env,
-            self_addr.clone().into_val(&env),
-            self_addr.clone().into_val(&env),
-            self_addr.clone().into_val(&env),
-            requests.into_val(&env),
-        ];
+        Self::credit_yield(&env, received);
+        Self::emit_blend_event(&env, symbol_short!("harvest"), &blend_pool, amount, received, min_return);
+
+        Self::unlock(&env);
+    }
+
+    /// Configure the permissionless `harvest`: at most `max_per_call` of accrued yield per
+    /// call, at least `min_interval_secs` between harvests, and `caller_reward_bps` of the
+    /// harvested amount paid to whoever triggers it.
+    pub fn set_harvest_policy(env: Env, max_per_call: i128, min_interval_secs: u64, caller_reward_bps: u32) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        assert!(max_per_call > 0, "max_per_call must be positive");
+        assert!(caller_reward_bps <= 10_000, "caller_reward_bps must be at most 10000");
+
+        env.storage().instance().set(
+            &DataKey::HarvestPolicy,
+            &HarvestPolicy {
+                max_per_call,
+                min_interval_secs,
+                caller_reward_bps,
+            },
+        );
+    }
+
+    pub fn get_harvest_policy(env: Env) -> Option<HarvestPolicy> {
+        env.storage().instance().get(&DataKey::HarvestPolicy)
+    }
+
+    pub fn get_last_harvest_time(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::LastHarvestTime)
+    }
+
+    /// Interest accrued across all Blend pools: position values minus recorded principal.
+    pub fn get_accrued_yield(env: Env) -> i128 {
+        let mut total: i128 = 0;
+        for alloc in Self::blend_pools(&env).iter() {
+            total += Self::accrued_yield(&env, &alloc.pool);
+        }
+        total
+    }
+
+    /// Share of harvested yield (in bps) that goes to the prize fund. The rest is added
+    /// to depositors' balances pro-rata. Defaults to 10000 (everything to the prize).
+    pub fn set_yield_split(env: Env, prize_bps: u32) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        assert!(prize_bps <= 10_000, "prize_bps must be at most 10000");
+        env.storage().instance().set(&DataKey::YieldSplitBps, &prize_bps);
+    }
+
+    pub fn get_yield_split(env: Env) -> u32 {
+        Self::yield_split(&env)
+    }
+
+    /// Nominal balance (and tickets / `period_days`) `user` will be credited at their
+    /// next interaction from yield distributed since they last settled.
+    pub fn get_pending_yield(env: Env, user: Address) -> i128 {
+        Self::pending_yield(&env, &user)
+    }
+
+    /// Anyone can harvest accrued Blend yield into the prize fund within the admin-set
+    /// policy. Pools are harvested in list order until `max_per_call` is used up. The
+    /// caller earns `caller_reward_bps` of what was harvested.
+    /// Returns the amount withdrawn from Blend (zero when nothing has accrued).
+    pub fn harvest(env: Env, caller: Address) -> i128 {
+        Self::require_initialized(&env);
+        caller.require_auth();
+        Self::lock(&env);
+
+        let policy: HarvestPolicy = env
+            .storage()
+            .instance()
+            .get(&DataKey::HarvestPolicy)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::HarvestPolicyNotSet));
+        let pools = Self::blend_pools(&env);
+        if pools.is_empty() {
+            panic_with_error!(&env, Error::BlendPoolNotSet);
+        }
+
+        let now = env.ledger().timestamp();
+        if let Some(last) = env.storage().instance().get::<_, u64>(&DataKey::LastHarvestTime) {
+            if now < last + policy.min_interval_secs {
+                panic_with_error!(&env, Error::HarvestTooEarly);
+            }
+        }
+
+        let token_id = Self::token_id(&env);
+        let mut budget = policy.max_per_call;
+        let mut received: i128 = 0;
+        for alloc in pools.iter() {
+            if budget <= 0 {
+                break;
+            }
+            let accrued = Self::accrued_yield(&env, &alloc.pool);
+            if accrued <= 0 {
+                continue;
+            }
+            let requested = accrued.min(budget);
+            let got = Self::withdraw_from_blend_pool(&env, &alloc.pool, &token_id, requested);
+            Self::emit_blend_event(&env, symbol_short!("harvest"), &alloc.pool, requested, got, 0);
+            budget -= requested;
+            received += got;
+        }
+        if received <= 0 {
+            Self::unlock(&env);
+            return 0;
+        }
+
+        let reward = received * policy.caller_reward_bps as i128 / 10_000;
+        if reward > 0 {
+            token::Client::new(&env, &token_id).transfer(&env.current_contract_address(), &caller, &reward);
+        }
+
+        Self::credit_yield(&env, received - reward);
+        env.storage().instance().set(&DataKey::LastHarvestTime, &now);
 
-        env.invoke_contract::<Val>(&blend_pool, &Symbol::new(&env, "submit"), args);
+        Self::unlock(&env);
+        received
+    }
+
+    /// Move the whole Blend position to `new_pool` in one invocation: withdraw everything
+    /// from the current pool, re-supply the recorded principal to the new one and route any
+    /// interest received into the prize fund.
+    pub fn migrate_blend_pool(env: Env, new_pool: Address, min_return: i128) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::lock(&env);
+        assert!(min_return >= 0, "min");
 
-        let balance_after = token::Client::new(&env, &token_id).balance(&self_addr);
-        let received = balance_after - balance_before;
+        let old_pool: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::BlendPool)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::BlendPoolNotSet));
+        assert!(old_pool != new_pool, "already using this blend pool");
+        Self::assert_blend_healthy(&env, &new_pool);
+
+        let token_id = Self::token_id(&env);
+        let position = Self::blend_position(&env, &old_pool);
+        let received = if position > 0 {
+            Self::withdraw_from_blend_pool(&env, &old_pool, &token_id, position)
+        } else {
+            0
+        };
         assert!(received >= min_return, "slip");
 
-        let prize: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap_or(0);
+        let supplied = Self::supplied_to(&env, &old_pool);
+        let principal = received.min(supplied);
+        let interest = received - principal;
+        if received < supplied {
+            Self::socialize_loss(&env, supplied - received);
+        }
+
+        env.storage().instance().set(&DataKey::BlendPool, &new_pool);
+        if principal > 0 {
+            Self::supply_to_blend_pool(&env, &new_pool, &token_id, principal);
+        }
+        Self::add_supplied(&env, &old_pool, -supplied);
+        Self::add_supplied(&env, &new_pool, principal);
+
+        // The new pool inherits the old pool's target weight
+        let mut pools = Self::blend_pools(&env);
+        if let Some(old_idx) = Self::pool_index(&pools, &old_pool) {
+            let old_weight = pools.get(old_idx).unwrap().weight_bps;
+            pools.remove(old_idx);
+            match Self::pool_index(&pools, &new_pool) {
+                Some(new_idx) => {
+                    let mut alloc = pools.get(new_idx).unwrap();
+                    alloc.weight_bps += old_weight;
+                    pools.set(new_idx, alloc);
+                }
+                None => pools.push_back(BlendAllocation {
+                    pool: new_pool.clone(),
+                    weight_bps: old_weight,
+                }),
+            }
+            env.storage().instance().set(&DataKey::BlendPools, &pools);
+        }
+
+        Self::credit_yield(&env, interest);
+
+        env.events().publish(
+            (symbol_short!("blend"), symbol_short!("migrate")),
+            (old_pool, new_pool, received, principal, interest),
+        );
+
+        Self::unlock(&env);
+    }
+
+    /// Record that `amount` of the principal supplied to Blend is gone (e.g. Blend bad
+    /// debt). Per-pool principal is written down in list order. The loss is spread over
+    /// every depositor through the global haircut factor instead of falling on whoever
+    /// withdraws last.
+    pub fn recognize_loss(env: Env, amount: i128) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        assert!(amount > 0, "loss must be positive");
+        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap_or(0);
+        assert!(amount <= Self::realizable(&env, total), "loss exceeds deposits");
+
+        // Write the principal down pool by pool in list order
+        let mut remaining = amount;
+        for alloc in Self::blend_pools(&env).iter() {
+            let cut = Self::supplied_to(&env, &alloc.pool).min(remaining);
+            Self::add_supplied(&env, &alloc.pool, -cut);
+            remaining -= cut;
+        }
+        Self::socialize_loss(&env, amount);
+    }
+
+    /// Current haircut factor, scaled by `HAIRCUT_SCALE` (`HAIRCUT_SCALE` = no loss).
+    pub fn get_haircut_factor(env: Env) -> i128 {
+        Self::haircut_factor(&env)
+    }
+
+    /// What `user` would receive for withdrawing their whole nominal balance.
+    pub fn get_realizable_balance(env: Env, user: Address) -> i128 {
+        let balance: i128 = env.storage().persistent().get(&DataKey::Balance(user)).unwrap_or(0);
+        Self::realizable(&env, balance)
+    }
+
+    /// Choose how funds are supplied to Blend: `BLEND_SUPPLY` (plain deposit, default) or
+    /// `BLEND_SUPPLY_COLLATERAL`. Withdrawals always use the matching withdraw type, so the
+    /// choice can only change while nothing is supplied.
+    pub fn set_blend_request_type(env: Env, request_type: u32) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        assert!(
+            request_type == BLEND_SUPPLY || request_type == BLEND_SUPPLY_COLLATERAL,
+            "request type must be 0 (supply) or 2 (supply collateral)"
+        );
+        let supplied: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::SuppliedToBlend)
+            .unwrap_or(0);
+        assert!(supplied == 0, "cannot change request type while funds are supplied");
+        env.storage().instance().set(&DataKey::BlendRequestType, &request_type);
+    }
+
+    pub fn get_blend_request_type(env: Env) -> u32 {
+        Self::supply_request_type(&env)
+    }
+
+    /// Let the next `supply_to_blend` skip the pool health check. Single use.
+    pub fn set_force_blend_supply(env: Env, enabled: bool) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        env.storage().instance().set(&DataKey::ForceBlendSupply, &enabled);
+    }
+
+    pub fn get_force_blend_supply(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::ForceBlendSupply).unwrap_or(false)
+    }
+
+    /// Status code reported by the primary Blend pool's `get_config`.
+    pub fn get_blend_pool_status(env: Env) -> Option<u32> {
         env.storage()
             .instance()
-            .set(&DataKey::PrizeFund, &(prize + received));
+            .get::<_, Address>(&DataKey::BlendPool)
+            .map(|pool| Self::blend_pool_status(&env, &pool))
     }
 
     pub fn get_blend_pool(env: Env) -> Option<Address> {
@@ -536,15 +1233,1098 @@ impl LuckyStakePool {
             .unwrap_or(0)
     }
 
+    /// Add `pool` to the Blend allocation list with a target share of `weight_bps`. The
+    /// first pool added also becomes the primary pool that deposits auto-supply into.
+    pub fn add_blend_pool(env: Env, pool: Address, weight_bps: u32) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+
+        let mut pools = Self::blend_pools(&env);
+        assert!(Self::pool_index(&pools, &pool).is_none(), "blend pool already added");
+        assert!(
+            Self::total_weight(&pools) + weight_bps <= 10_000,
+            "weights must not exceed 10000 bps"
+        );
+        pools.push_back(BlendAllocation {
+            pool: pool.clone(),
+            weight_bps,
+        });
+        env.storage().instance().set(&DataKey::BlendPools, &pools);
+        if !env.storage().instance().has(&DataKey::BlendPool) {
+            env.storage().instance().set(&DataKey::BlendPool, &pool);
+        }
+        if !env.storage().instance().has(&DataKey::SuppliedToBlend) {
+            env.storage().instance().set(&DataKey::SuppliedToBlend, &0i128);
+        }
+        env.events().publish(
+            (symbol_short!("blend"), symbol_short!("add_pool")),
+            (pool, weight_bps),
+        );
+    }
+
+    /// Drop `pool` from the allocation list. Only allowed once nothing is supplied to it.
+    pub fn remove_blend_pool(env: Env, pool: Address) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+
+        let mut pools = Self::blend_pools(&env);
+        let idx = Self::pool_index(&pools, &pool).unwrap_or_else(|| panic!("unknown blend pool"));
+        assert!(
+            Self::supplied_to(&env, &pool) == 0 && Self::blend_position(&env, &pool) == 0,
+            "blend pool still holds funds"
+        );
+        pools.remove(idx);
+        env.storage().instance().set(&DataKey::BlendPools, &pools);
+        env.storage().instance().remove(&DataKey::SuppliedTo(pool.clone()));
+
+        let primary: Option<Address> = env.storage().instance().get(&DataKey::BlendPool);
+        if primary == Some(pool.clone()) {
+            match pools.first() {
+                Some(next) => env.storage().instance().set(&DataKey::BlendPool, &next.pool),
+                None => env.storage().instance().remove(&DataKey::BlendPool),
+            }
+        }
+        env.events()
+            .publish((symbol_short!("blend"), symbol_short!("rm_pool")), pool);
+    }
+
+    /// Replace every pool's target weight. `weights` must name exactly the listed pools
+    /// and sum to 10000 bps.
+    pub fn set_blend_weights(env: Env, weights: Map<Address, u32>) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+
+        let mut pools = Self::blend_pools(&env);
+        assert!(weights.len() == pools.len(), "weights must cover every blend pool");
+        let mut updated: Vec<BlendAllocation> = Vec::new(&env);
+        for alloc in pools.iter() {
+            let weight_bps = weights
+                .get(alloc.pool.clone())
+                .unwrap_or_else(|| panic!("weights must cover every blend pool"));
+            updated.push_back(BlendAllocation {
+                pool: alloc.pool,
+                weight_bps,
+            });
+        }
+        pools = updated;
+        assert!(Self::total_weight(&pools) == 10_000, "weights must sum to 10000 bps");
+        env.storage().instance().set(&DataKey::BlendPools, &pools);
+    }
+
+    /// Move supplied principal between Blend pools towards their target weights. Pools
+    /// within `REBALANCE_TOLERANCE_BPS` of their target are left alone; overweight pools
+    /// are drawn down first and the proceeds supplied to underweight ones. Each withdrawal
+    /// must return all but `max_slippage_bps` of the excess it requests, and whatever
+    /// does not come back is booked as a loss.
+    pub fn rebalance(env: Env, max_slippage_bps: u32) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::lock(&env);
+        assert!(max_slippage_bps <= 10_000, "max_slippage_bps must be at most 10000");
+
+        let pools = Self::blend_pools(&env);
+        assert!(Self::total_weight(&pools) == 10_000, "weights must sum to 10000 bps");
+        let token_id = Self::token_id(&env);
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::SuppliedToBlend)
+            .unwrap_or(0);
+        let tolerance = total * REBALANCE_TOLERANCE_BPS / 10_000;
+
+        let mut freed: i128 = 0;
+        for alloc in pools.iter() {
+            let target = total * alloc.weight_bps as i128 / 10_000;
+            let excess = Self::supplied_to(&env, &alloc.pool) - target;
+            if excess > tolerance {
+                let min_return = excess * (10_000 - max_slippage_bps as i128) / 10_000;
+                let received = Self::withdraw_principal(&env, &alloc.pool, &token_id, excess);
+                assert!(received >= min_return, "slip");
+                Self::emit_blend_event(&env, symbol_short!("withdraw"), &alloc.pool, excess, received, min_return);
+                freed += received;
+            }
+        }
+
+        for alloc in pools.iter() {
+            if freed <= 0 {
+                break;
+            }
+            let target = total * alloc.weight_bps as i128 / 10_000;
+            let deficit = target - Self::supplied_to(&env, &alloc.pool);
+            if deficit > tolerance && Self::blend_pool_status(&env, &alloc.pool) <= BLEND_STATUS_ACTIVE {
+                let amount = deficit.min(freed);
+                Self::supply_to_blend_pool(&env, &alloc.pool, &token_id, amount);
+                Self::add_supplied(&env, &alloc.pool, amount);
+                Self::emit_blend_event(&env, symbol_short!("supply"), &alloc.pool, amount, amount, amount);
+                freed -= amount;
+            }
+        }
+
+        Self::unlock(&env);
+    }
+
+    /// Every Blend pool with its target weight (bps) and recorded principal.
+    pub fn get_blend_pools(env: Env) -> Vec<(Address, u32, i128)> {
+        let mut out = Vec::new(&env);
+        for alloc in Self::blend_pools(&env).iter() {
+            let supplied = Self::supplied_to(&env, &alloc.pool);
+            out.push_back((alloc.pool, alloc.weight_bps, supplied));
+        }
+        out
+    }
+
     pub fn set_receipt_token(env: Env, receipt_token: Address) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let admin = Self::admin(&env);
         admin.require_auth();
+        Self::assert_unlocked(&env);
         env.storage().instance().set(&DataKey::ReceiptToken, &receipt_token);
     }
 
     pub fn get_receipt_token(env: Env) -> Option<Address> {
         env.storage().instance().get(&DataKey::ReceiptToken)
     }
+
+    // ── Pool share token (SEP-41) ──────────────────────────────────────────────
+    // One share per unit of nominal balance: minted on deposit, burned on withdraw.
+    // Moving shares moves the balance and a proportional slice of tickets with it.
+
+    pub fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        match env
+            .storage()
+            .temporary()
+            .get::<_, AllowanceValue>(&DataKey::Allowance(from, spender))
+        {
+            Some(a) if a.expiration_ledger >= env.ledger().sequence() => a.amount,
+            _ => 0,
+        }
+    }
+
+    pub fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        Self::assert_unlocked(&env);
+        assert!(amount >= 0, "negative amount");
+        assert!(
+            amount == 0 || expiration_ledger >= env.ledger().sequence(),
+            "expiration_ledger is in the past"
+        );
+        Self::write_allowance(&env, &from, &spender, amount, expiration_ledger);
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&DataKey::Balance(id)).unwrap_or(0)
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::assert_unlocked(&env);
+        Self::move_shares(&env, &from, &to, amount);
+    }
+
+    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::assert_unlocked(&env);
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::move_shares(&env, &from, &to, amount);
+    }
+
+    pub fn decimals(env: Env) -> u32 {
+        token::Client::new(&env, &Self::token_id(&env)).decimals()
+    }
+
+    pub fn name(env: Env) -> String {
+        String::from_str(&env, "LuckyStake Pool Share")
+    }
+
+    pub fn symbol(env: Env) -> String {
+        match Self::period_days(&env) {
+            7 => String::from_str(&env, "LSP7"),
+            15 => String::from_str(&env, "LSP15"),
+            _ => String::from_str(&env, "LSP30"),
+        }
+    }
+
+    /// When enabled, `execute_draw` pulls any prize liquidity shortfall back from Blend
+    /// instead of failing with `InsufficientLiquidityForPrize`.
+    pub fn set_auto_harvest(env: Env, enabled: bool) {
+        let admin = Self::admin(&env);
+        admin.require_auth();
+        Self::assert_unlocked(&env);
+        env.storage().instance().set(&DataKey::AutoHarvest, &enabled);
+    }
+
+    pub fn get_auto_harvest(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::AutoHarvest).unwrap_or(false)
+    }
+}
+
+impl LuckyStakePool {
+    /// Remove `amount` of principal from `depositor`'s position: balance, proportional
+    /// tickets, pool totals and receipt tokens. Moves no pool tokens.
+    fn debit_position(env: &Env, depositor: &Address, amount: i128) {
+        let balance: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Balance(depositor.clone()))
+            .unwrap_or(0);
+        assert!(balance >= amount, "insufficient balance");
+
+        let tickets: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Tickets(depositor.clone()))
+            .unwrap_or(0);
+
+        let tickets_to_remove = if balance > 0 {
+            (tickets * amount) / balance
+        } else {
+            0i128
+        };
+
+        let new_balance = balance - amount;
+        let new_tickets = tickets - tickets_to_remove;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Balance(depositor.clone()), &new_balance);
+        Self::set_tickets(env, depositor, new_tickets);
+
+        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap();
+        let total_tickets: i128 = env.storage().instance().get(&DataKey::TotalTickets).unwrap();
+
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalDeposits, &(total - amount));
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalTickets, &(total_tickets - tickets_to_remove));
+        Self::tickets_changed(env);
+
+        env.events()
+            .publish((symbol_short!("burn"), depositor.clone()), amount);
+
+        // Burn Receipt Tokens from User
+        if let Some(receipt_token_id) = env.storage().instance().get::<_, Address>(&DataKey::ReceiptToken) {
+            let receipt_client = token::Client::new(env, &receipt_token_id);
+            // Move tokens back to pool (or burn if the token supports it)
+            receipt_client.transfer(depositor, &env.current_contract_address(), &amount);
+        }
+    }
+
+    fn add_depositor(env: &Env, depositor: &Address) {
+        let mut depositors: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Depositors)
+            .unwrap_or_else(|| Vec::new(env));
+        if !depositors.contains(depositor) {
+            depositors.push_back(depositor.clone());
+            env.storage().persistent().set(&DataKey::Depositors, &depositors);
+        }
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let allowance = Self::allowance(env.clone(), from.clone(), spender.clone());
+        assert!(allowance >= amount, "insufficient allowance");
+        let key = DataKey::Allowance(from.clone(), spender.clone());
+        let value: AllowanceValue = env.storage().temporary().get(&key).unwrap();
+        Self::write_allowance(env, from, spender, value.amount - amount, value.expiration_ledger);
+    }
+
+    /// Allowances live in temporary storage until their expiration ledger, as in the
+    /// SEP-41 reference token, so approvals never grow the instance entry.
+    fn write_allowance(env: &Env, from: &Address, spender: &Address, amount: i128, expiration_ledger: u32) {
+        let key = DataKey::Allowance(from.clone(), spender.clone());
+        env.storage().temporary().set(
+            &key,
+            &AllowanceValue {
+                amount,
+                expiration_ledger,
+            },
+        );
+        if amount > 0 {
+            let live_for = expiration_ledger - env.ledger().sequence();
+            env.storage().temporary().extend_ttl(&key, live_for, live_for);
+        }
+    }
+
+    /// Share transfer hook: move `amount` of nominal balance and the matching share of
+    /// tickets from `from` to `to`. Pool totals are unchanged.
+    fn move_shares(env: &Env, from: &Address, to: &Address, amount: i128) {
+        assert!(amount >= 0, "negative amount");
+        Self::settle_yield(env, from);
+        Self::settle_yield(env, to);
+
+        let from_balance: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Balance(from.clone()))
+            .unwrap_or(0);
+        assert!(from_balance >= amount, "insufficient balance");
+        if from != to && amount > 0 {
+            let from_tickets: i128 = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Tickets(from.clone()))
+                .unwrap_or(0);
+            let tickets = from_tickets * amount / from_balance;
+            let to_balance: i128 = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Balance(to.clone()))
+                .unwrap_or(0);
+            let to_tickets: i128 = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Tickets(to.clone()))
+                .unwrap_or(0);
+
+            env.storage()
+                .persistent()
+                .set(&DataKey::Balance(from.clone()), &(from_balance - amount));
+            Self::set_tickets(env, from, from_tickets - tickets);
+            env.storage()
+                .persistent()
+                .set(&DataKey::Balance(to.clone()), &(to_balance + amount));
+            Self::set_tickets(env, to, to_tickets + tickets);
+            Self::add_depositor(env, to);
+            Self::tickets_changed(env);
+        }
+
+        env.events().publish(
+            (symbol_short!("transfer"), from.clone(), to.clone()),
+            amount,
+        );
+    }
+
+    /// Move `amount` of `user`'s queued withdrawal to their claimable balance and
+    /// reserve the idle tokens backing it.
+    fn mature_claim(env: &Env, user: &Address, amount: i128) {
+        let queued: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::QueuedAmount(user.clone()))
+            .unwrap_or(0);
+        if queued == amount {
+            env.storage().persistent().remove(&DataKey::QueuedAmount(user.clone()));
+        } else {
+            env.storage()
+                .persistent()
+                .set(&DataKey::QueuedAmount(user.clone()), &(queued - amount));
+        }
+        let queued_total: i128 = env.storage().instance().get(&DataKey::QueuedTotal).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::QueuedTotal, &(queued_total - amount));
+
+        let claimable: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ClaimableWithdrawal(user.clone()))
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&DataKey::ClaimableWithdrawal(user.clone()), &(claimable + amount));
+        let reserved: i128 = env.storage().instance().get(&DataKey::ReservedForClaims).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::ReservedForClaims, &(reserved + amount));
+    }
+
+    /// Idle tokens that must never go to Blend: the prize fund, admin yield and reserved
+    /// claims (idle user principal is meant to be supplied).
+    fn prize_reserved(env: &Env) -> i128 {
+        let prize: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap_or(0);
+        let admin_yield: i128 = env.storage().instance().get(&DataKey::AdminYield).unwrap_or(0);
+        let reserved: i128 = env.storage().instance().get(&DataKey::ReservedForClaims).unwrap_or(0);
+        prize + admin_yield + reserved
+    }
+
+    /// Idle tokens not already spoken for by the prize fund, admin yield, idle user
+    /// principal or matured withdrawal claims.
+    fn free_idle(env: &Env) -> i128 {
+        let token_id = Self::token_id(env);
+        let idle = token::Client::new(env, &token_id).balance(&env.current_contract_address());
+        (idle - Self::prize_reserved(env) - Self::idle_principal(env)).max(0)
+    }
+
+    fn emit_blend_event(env: &Env, op: Symbol, pool: &Address, requested: i128, received: i128, min_return: i128) {
+        let supplied: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::SuppliedToBlend)
+            .unwrap_or(0);
+        env.events().publish(
+            (symbol_short!("blend"), op),
+            BlendEvent {
+                pool: pool.clone(),
+                requested,
+                received,
+                min_return,
+                supplied,
+            },
+        );
+    }
+
+    fn supply_request_type(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::BlendRequestType)
+            .unwrap_or(BLEND_SUPPLY)
+    }
+
+    /// Withdraw type mirroring the configured supply type (1 for 0, 3 for 2).
+    fn withdraw_request_type(env: &Env) -> u32 {
+        if Self::supply_request_type(env) == BLEND_SUPPLY_COLLATERAL {
+            BLEND_WITHDRAW_COLLATERAL
+        } else {
+            BLEND_WITHDRAW
+        }
+    }
+
+    /// Approve `blend_pool` for exactly `amount` and supply it via `submit_with_allowance`.
+    fn supply_to_blend_pool(env: &Env, blend_pool: &Address, token_id: &Address, amount: i128) {
+        let self_addr = env.current_contract_address();
+        let expiration = env.ledger().sequence() + 50_000;
+        token::Client::new(env, token_id).approve(&self_addr, blend_pool, &amount, &expiration);
+
+        let requests = blend::single_request(env, Self::supply_request_type(env), token_id, amount);
+        BlendClient::new(env, blend_pool).submit_with_allowance(&self_addr, &self_addr, &self_addr, &requests);
+    }
+
+    /// Withdraw `amount` of `token_id` from `blend_pool` back to this contract.
+    /// Returns what actually arrived, measured by balance delta.
+    fn withdraw_from_blend_pool(env: &Env, blend_pool: &Address, token_id: &Address, amount: i128) -> i128 {
+        let token_client = token::Client::new(env, token_id);
+        let self_addr = env.current_contract_address();
+        let balance_before = token_client.balance(&self_addr);
+
+        let requests = blend::single_request(env, Self::withdraw_request_type(env), token_id, amount);
+        BlendClient::new(env, blend_pool).submit(&self_addr, &self_addr, &self_addr, &requests);
+
+        token_client.balance(&self_addr) - balance_before
+    }
+
+    /// Withdraw `amount` of supplied principal from `blend_pool` and debit its recorded
+    /// principal by the full request. Whatever Blend does not pay back is gone, so it is
+    /// booked as a loss like `recognize_loss` instead of lingering as phantom principal.
+    fn withdraw_principal(env: &Env, blend_pool: &Address, token_id: &Address, amount: i128) -> i128 {
+        let received = Self::withdraw_from_blend_pool(env, blend_pool, token_id, amount);
+        Self::add_supplied(env, blend_pool, -amount);
+        if received < amount {
+            Self::socialize_loss(env, amount - received);
+        }
+        received
+    }
+
+    /// Current value of this contract's Blend position (supply + collateral).
+    fn blend_position(env: &Env, blend_pool: &Address) -> i128 {
+        BlendClient::new(env, blend_pool)
+            .get_positions(&env.current_contract_address())
+            .total_supplied()
+    }
+
+    fn blend_pool_status(env: &Env, blend_pool: &Address) -> u32 {
+        BlendClient::new(env, blend_pool).get_config().status
+    }
+
+    /// Reject supplying to a pool that is not active, unless the admin forced it
+    /// (which consumes the force flag).
+    fn assert_blend_healthy(env: &Env, blend_pool: &Address) {
+        let forced: bool = env.storage().instance().get(&DataKey::ForceBlendSupply).unwrap_or(false);
+        if forced {
+            env.storage().instance().set(&DataKey::ForceBlendSupply, &false);
+            return;
+        }
+        if Self::blend_pool_status(env, blend_pool) > BLEND_STATUS_ACTIVE {
+            panic_with_error!(env, Error::BlendPoolUnhealthy);
+        }
+    }
+
+    fn accrued_yield(env: &Env, blend_pool: &Address) -> i128 {
+        (Self::blend_position(env, blend_pool) - Self::supplied_to(env, blend_pool)).max(0)
+    }
+
+    fn blend_pools(env: &Env) -> Vec<BlendAllocation> {
+        env.storage()
+            .instance()
+            .get(&DataKey::BlendPools)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn pool_index(pools: &Vec<BlendAllocation>, pool: &Address) -> Option<u32> {
+        pools.iter().position(|a| a.pool == *pool).map(|i| i as u32)
+    }
+
+    fn total_weight(pools: &Vec<BlendAllocation>) -> u32 {
+        pools.iter().map(|a| a.weight_bps).sum()
+    }
+
+    fn supplied_to(env: &Env, pool: &Address) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SuppliedTo(pool.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Adjust `pool`'s recorded principal by `delta` (never below zero) and keep the
+    /// aggregate `SuppliedToBlend` equal to the sum over all pools.
+    fn add_supplied(env: &Env, pool: &Address, delta: i128) {
+        let before = Self::supplied_to(env, pool);
+        let after = (before + delta).max(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::SuppliedTo(pool.clone()), &after);
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::SuppliedToBlend)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::SuppliedToBlend, &(total + after - before));
+    }
+
+    fn require_initialized(env: &Env) {
+        if !env.storage().instance().has(&DataKey::Admin) {
+            panic_with_error!(env, Error::NotInitialized);
+        }
+    }
+
+    fn admin(env: &Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
+
+    fn token_id(env: &Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Token)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
+
+    fn period_days(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::PeriodDays)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
+
+    /// Reentrancy guard: every state-mutating entrypoint that calls out to the token
+    /// or Blend holds the lock for its whole duration. A panic reverts the flag too.
+    fn lock(env: &Env) {
+        Self::assert_unlocked(env);
+        env.storage().instance().set(&DataKey::Locked, &true);
+    }
+
+    fn unlock(env: &Env) {
+        env.storage().instance().remove(&DataKey::Locked);
+    }
+
+    fn assert_unlocked(env: &Env) {
+        if env.storage().instance().has(&DataKey::Locked) {
+            panic_with_error!(env, Error::ReentrancyDetected);
+        }
+    }
+
+    fn min_draw_interval(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::MinDrawInterval)
+            .unwrap_or_else(|| Self::period_days(env) as u64 * 86_400)
+    }
+
+    /// Reject draws that come too soon after the previous one (unless the admin
+    /// override is set), then stamp the current time as the last draw time.
+    fn enforce_draw_interval(env: &Env) {
+        let now = env.ledger().timestamp();
+        let overridden: bool = env.storage().instance().get(&DataKey::DrawOverride).unwrap_or(false);
+        if overridden {
+            env.storage().instance().set(&DataKey::DrawOverride, &false);
+        } else if let Some(last) = env.storage().instance().get::<_, u64>(&DataKey::LastDrawTime) {
+            if now < last + Self::min_draw_interval(env) {
+                panic_with_error!(env, Error::DrawTooEarly);
+            }
+        }
+        env.storage().instance().set(&DataKey::LastDrawTime, &now);
+    }
+
+    /// The random number picking draw `nonce`'s winning ticket, from the ledger PRNG.
+    fn draw_random(env: &Env, nonce: u64) -> u64 {
+        #[cfg(any(test, feature = "deterministic-draws"))]
+        if let Some(random) = testing::seeded_random(env, nonce) {
+            let head = random.to_array();
+            env.prng().seed(random.into());
+            return u64::from_be_bytes(head[..8].try_into().unwrap());
+        }
+        #[cfg(not(any(test, feature = "deterministic-draws")))]
+        let _ = nonce;
+        env.prng().gen()
+    }
+
+    /// Body of `deposit`; the caller authorized and locked.
+    fn deposit_inner(env: &Env, depositor: &Address, amount: i128) {
+        assert!(amount > 0, "deposit amount must be greater than zero");
+        let weight = Self::draw_tickets(env, depositor);
+        Self::settle_yield(env, depositor);
+
+        let token_id = Self::token_id(env);
+        let token_client = token::Client::new(env, &token_id);
+        let self_addr = env.current_contract_address();
+
+        // 1. Transfer from User to Contract
+        token_client.transfer(depositor, &self_addr, &amount);
+
+        // After a recognized loss, credit the nominal balance whose realizable value is `amount`
+        let nominal = Self::nominal_for(env, amount);
+
+        // 2. Automatically Supply to Blend (if pool is configured and active; otherwise the
+        //    tokens stay idle until the admin supplies them)
+        let blend_pool = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::BlendPool)
+            .filter(|pool| Self::blend_pool_status(env, pool) <= BLEND_STATUS_ACTIVE);
+        if let Some(blend_pool) = blend_pool {
+            // Atomic invocation to Blend
+            Self::supply_to_blend_pool(env, &blend_pool, &token_id, amount);
+
+            // Emit a "Token Receipt" event for the user to verify on the explorer
+            env.events().publish(
+                (Symbol::new(env, "LUCKY_STAKED"), depositor.clone()),
+                amount
+            );
+
+            // Update internal tracking
+            Self::add_supplied(env, &blend_pool, amount);
+            Self::emit_blend_event(env, symbol_short!("supply"), &blend_pool, amount, amount, amount);
+        }
+
+        // 3. Mint Receipt Tokens to User, whether or not the deposit reached Blend:
+        //    `debit_position` takes them back on every withdrawal
+        if let Some(receipt_token_id) = env.storage().instance().get::<_, Address>(&DataKey::ReceiptToken) {
+            let receipt_client = token::Client::new(env, &receipt_token_id);
+            // Note: Pool contract must be the admin of the Receipt Token to mint
+            receipt_client.transfer(&self_addr, depositor, &nominal);
+        }
+
+        // 4. Update User State (Tickets and Balance)
+        let period_days = Self::period_days(env);
+        let tickets_to_add = nominal * (period_days as i128);
+
+        let current_balance: i128 = env.storage().persistent().get(&DataKey::Balance(depositor.clone())).unwrap_or(0);
+        let current_tickets: i128 = env.storage().persistent().get(&DataKey::Tickets(depositor.clone())).unwrap_or(0);
+
+        env.storage().persistent().set(&DataKey::Balance(depositor.clone()), &(current_balance + nominal));
+        Self::set_tickets(env, depositor, current_tickets + tickets_to_add);
+
+        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap();
+        let total_tickets: i128 = env.storage().instance().get(&DataKey::TotalTickets).unwrap();
+
+        env.storage().instance().set(&DataKey::TotalDeposits, &(total + nominal));
+        env.storage().instance().set(&DataKey::TotalTickets, &(total_tickets + tickets_to_add));
+
+        // Anyone already holding a balance or tickets is on the list
+        if current_balance <= 0 && current_tickets <= 0 {
+            Self::add_depositor(env, depositor);
+        }
+        Self::weight_changed(env, depositor, weight);
+        env.events().publish((symbol_short!("mint"), self_addr, depositor.clone()), nominal);
+    }
+
+    /// Every depositor's draw weight, read-only, and their sum.
+    fn scan_participants(
+        env: &Env,
+        depositors: &Vec<Address>,
+        snapshot: Option<u64>,
+    ) -> (Vec<(Address, i128)>, i128) {
+        let mut participants: Vec<(Address, i128)> = Vec::new(env);
+        let mut acc: i128 = 0;
+        for d in depositors.iter() {
+            // Read-only: settling every depositor here would rewrite instance storage
+            // once per depositor and make the draw cost grow with the pool's size. The
+            // same steps as `draw_tickets`, with the snapshot time read once.
+            let t = Self::draw_tickets_at(env, &d, snapshot);
+            if t > 0 {
+                acc += t;
+                participants.push_back((d.clone(), t));
+            }
+        }
+        (participants, acc)
+    }
+
+    /// Write index chunk `chunk_id` and record its running total in `index`.
+    fn store_index_chunk(env: &Env, index: &mut DrawIndex, chunk_id: u32, chunk: &Vec<(Address, i128)>) {
+        env.storage().persistent().set(&DataKeyExt::IndexChunk(chunk_id), chunk);
+        if chunk_id < index.chunk_ends.len() {
+            index.chunk_ends.set(chunk_id, index.total);
+        } else {
+            index.chunk_ends.push_back(index.total);
+        }
+    }
+
+    /// Bump the ticket version, invalidating the draw index. Called wherever a draw
+    /// weight can change.
+    fn tickets_changed(env: &Env) {
+        let version: u64 = env.storage().instance().get(&DataKeyExt::TicketsVersion).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKeyExt::TicketsVersion, &(version + 1));
+    }
+
+    /// `tickets_changed` after a ticket change of `user`, unless their draw weight is still
+    /// `before`: tickets gained after the snapshot time stay pending until the epoch rolls,
+    /// so the index built for this draw still holds. A depositor appended since the build
+    /// with no weight is passed over as `rebuild_index` would.
+    fn weight_changed(env: &Env, user: &Address, before: i128) {
+        if Self::draw_tickets(env, user) != before {
+            return Self::tickets_changed(env);
+        }
+        if before != 0 {
+            return;
+        }
+        let version: u64 = env.storage().instance().get(&DataKeyExt::TicketsVersion).unwrap_or(0);
+        let Some(mut index) = env
+            .storage()
+            .instance()
+            .get::<_, DrawIndex>(&DataKeyExt::DrawIndex)
+            .filter(|index| index.version == version)
+        else {
+            return;
+        };
+        let depositors: Vec<Address> = env.storage().persistent().get(&DataKey::Depositors).unwrap();
+        if index.next + 1 != depositors.len() || depositors.last().as_ref() != Some(user) {
+            return;
+        }
+        index.next += 1;
+        env.storage().instance().set(&DataKeyExt::DrawIndex, &index);
+    }
+
+    /// The draw index, if it covers all `depositors` at the current ticket version.
+    fn ready_index(env: &Env, depositors: u32) -> Option<DrawIndex> {
+        let version: u64 = env.storage().instance().get(&DataKeyExt::TicketsVersion).unwrap_or(0);
+        env.storage()
+            .instance()
+            .get::<_, DrawIndex>(&DataKeyExt::DrawIndex)
+            .filter(|index| index.version == version && index.next == depositors && index.total > 0)
+    }
+
+    /// Owner of ticket `ticket` (`< index.total`): binary search over the chunk ends,
+    /// then over the chunk's cumulative entries.
+    fn index_winner(env: &Env, index: &DrawIndex, ticket: i128) -> Address {
+        let (mut lo, mut hi) = (0u32, index.chunk_ends.len() - 1);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if ticket < index.chunk_ends.get(mid).unwrap() {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        let chunk: Vec<(Address, i128)> = env
+            .storage()
+            .persistent()
+            .get(&DataKeyExt::IndexChunk(lo))
+            .unwrap();
+        let (mut lo, mut hi) = (0u32, chunk.len() - 1);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if ticket < chunk.get(mid).unwrap().1 {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        chunk.get(lo).unwrap().0
+    }
+
+    /// Owner of ticket `ticket`, through the index when there is one and the scanned
+    /// participants otherwise.
+    fn ticket_owner(
+        env: &Env,
+        index: Option<&DrawIndex>,
+        participants: Option<&Vec<(Address, i128)>>,
+        ticket: i128,
+    ) -> Address {
+        if let Some(index) = index {
+            return Self::index_winner(env, index, ticket);
+        }
+        let participants = participants.unwrap();
+        participants.get_unchecked(selection::locate_ticket(participants, ticket).0).0
+    }
+
+    fn record_draw(env: &Env, result: DrawResult) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::DrawResult(result.nonce), &result);
+    }
+
+    fn haircut_factor(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::HaircutFactor)
+            .unwrap_or(HAIRCUT_SCALE)
+    }
+
+    /// Token value of `nominal` balance under the current haircut, rounded down so the
+    /// sum of realizable balances never exceeds the realizable total.
+    fn realizable(env: &Env, nominal: i128) -> i128 {
+        nominal * Self::haircut_factor(env) / HAIRCUT_SCALE
+    }
+
+    /// Nominal balance credited for depositing `amount` tokens, rounded down so a new
+    /// deposit never realizes more than it brought in.
+    fn nominal_for(env: &Env, amount: i128) -> i128 {
+        let factor = Self::haircut_factor(env);
+        if factor == 0 {
+            panic!("pool is fully written off");
+        }
+        amount * HAIRCUT_SCALE / factor
+    }
+
+    /// Lower the haircut factor so that the realizable total drops by at least `loss`.
+    /// The new factor is rounded down, keeping total claims at or below actual assets.
+    fn socialize_loss(env: &Env, loss: i128) {
+        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap_or(0);
+        let realizable_total = Self::realizable(env, total);
+        let factor = if total > 0 {
+            (realizable_total - loss).max(0) * HAIRCUT_SCALE / total
+        } else {
+            Self::haircut_factor(env)
+        };
+        env.storage().instance().set(&DataKey::HaircutFactor, &factor);
+        env.events()
+            .publish((symbol_short!("pool"), symbol_short!("loss")), (loss, factor));
+    }
+
+    fn yield_split(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::YieldSplitBps)
+            .unwrap_or(10_000)
+    }
+
+    fn yield_index(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKey::YieldIndex).unwrap_or(0)
+    }
+
+    fn unsettled_yield(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKeyExt2::UnsettledYield).unwrap_or(0)
+    }
+
+    fn pending_yield(env: &Env, user: &Address) -> i128 {
+        let balance: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Balance(user.clone()))
+            .unwrap_or(0);
+        let user_index: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserYieldIndex(user.clone()))
+            .unwrap_or(0);
+        balance * (Self::yield_index(env) - user_index) / YIELD_INDEX_SCALE
+    }
+
+    /// When the next draw's tickets are fixed: the snapshot offset before the next draw
+    /// time. `None` without an offset or before the first draw.
+    fn snapshot_time(env: &Env) -> Option<u64> {
+        let offset: u64 = env.storage().instance().get(&DataKeyExt2::SnapshotOffset)?;
+        let last: u64 = env.storage().instance().get(&DataKey::LastDrawTime)?;
+        Some((last + Self::min_draw_interval(env)).saturating_sub(offset))
+    }
+
+    /// Tickets `user` gained after the snapshot time `at`, which the draw leaves out.
+    fn tickets_since(env: &Env, user: &Address, at: u64) -> i128 {
+        let Some(checkpoint) = env
+            .storage()
+            .persistent()
+            .get::<_, TicketCheckpoint>(&DataKeyExt2::TicketCheckpoint(user.clone()))
+            .filter(|checkpoint| checkpoint.changed_at > at)
+        else {
+            return 0;
+        };
+        let tickets: i128 = env.storage().persistent().get(&DataKey::Tickets(user.clone())).unwrap_or(0);
+        (tickets - checkpoint.counted).max(0)
+    }
+
+    /// Store `user`'s tickets, tracking the least they held since the snapshot time in a
+    /// `TicketCheckpoint` once it has passed; a change before it drops the checkpoint.
+    fn set_tickets(env: &Env, user: &Address, tickets: i128) {
+        let key = DataKeyExt2::TicketCheckpoint(user.clone());
+        let now = env.ledger().timestamp();
+        match Self::snapshot_time(env) {
+            Some(at) if now > at => {
+                let checkpoint = env
+                    .storage()
+                    .persistent()
+                    .get::<_, TicketCheckpoint>(&key)
+                    .filter(|checkpoint| checkpoint.changed_at > at);
+                let counted = match checkpoint {
+                    Some(checkpoint) => checkpoint.counted,
+                    None => env.storage().persistent().get(&DataKey::Tickets(user.clone())).unwrap_or(0),
+                };
+                env.storage().persistent().set(
+                    &key,
+                    &TicketCheckpoint {
+                        counted: counted.min(tickets),
+                        changed_at: now,
+                    },
+                );
+            }
+            Some(_) => env.storage().persistent().remove(&key),
+            // Without a snapshot time no checkpoint is read, so none is touched
+            None => {}
+        }
+        env.storage().persistent().set(&DataKey::Tickets(user.clone()), &tickets);
+    }
+
+    /// `user`'s tickets including those their unsettled yield will add.
+    fn effective_tickets(env: &Env, user: &Address) -> i128 {
+        let tickets: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Tickets(user.clone()))
+            .unwrap_or(0);
+        tickets + Self::pending_yield(env, user) * Self::period_days(env) as i128
+    }
+
+    /// `user`'s weight in the current epoch's draw: `effective_tickets` less the tickets
+    /// gained after the snapshot time.
+    fn draw_tickets(env: &Env, user: &Address) -> i128 {
+        Self::draw_tickets_at(env, user, Self::snapshot_time(env))
+    }
+
+    /// `draw_tickets` for a draw whose tickets are fixed at `snapshot`.
+    fn draw_tickets_at(env: &Env, user: &Address, snapshot: Option<u64>) -> i128 {
+        let late = snapshot.map_or(0, |at| Self::tickets_since(env, user, at));
+        Self::effective_tickets(env, user) - late
+    }
+
+    /// Split harvested yield between the prize fund and depositors. The depositor share
+    /// is added to the pool totals now and to each balance lazily via the yield index.
+    fn credit_yield(env: &Env, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap_or(0);
+        // Pending yield earns nothing until settled, so only settled balances share
+        let unsettled = Self::unsettled_yield(env);
+        let settled = total - unsettled;
+        let mut to_prize = amount * Self::yield_split(env) as i128 / 10_000;
+        let share = amount - to_prize;
+        if share > 0 && settled > 0 && Self::haircut_factor(env) > 0 {
+            let nominal = Self::nominal_for(env, share);
+            env.storage().instance().set(
+                &DataKey::YieldIndex,
+                &(Self::yield_index(env) + nominal * YIELD_INDEX_SCALE / settled),
+            );
+            env.storage()
+                .instance()
+                .set(&DataKeyExt2::UnsettledYield, &(unsettled + nominal));
+            let total_tickets: i128 = env.storage().instance().get(&DataKey::TotalTickets).unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&DataKey::TotalDeposits, &(total + nominal));
+            env.storage().instance().set(
+                &DataKey::TotalTickets,
+                &(total_tickets + nominal * Self::period_days(env) as i128),
+            );
+            Self::tickets_changed(env);
+        } else {
+            // Nobody to share with
+            to_prize = amount;
+        }
+
+        let prize: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::PrizeFund, &(prize + to_prize));
+    }
+
+    /// Credit `user`'s pending yield to their balance and tickets. Pool totals already
+    /// include it (see `credit_yield`).
+    fn settle_yield(env: &Env, user: &Address) {
+        let pending = Self::pending_yield(env, user);
+        if pending > 0 {
+            let balance: i128 = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Balance(user.clone()))
+                .unwrap_or(0);
+            let tickets: i128 = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Tickets(user.clone()))
+                .unwrap_or(0);
+            env.storage()
+                .persistent()
+                .set(&DataKey::Balance(user.clone()), &(balance + pending));
+            Self::set_tickets(env, user, tickets + pending * Self::period_days(env) as i128);
+            env.storage().instance().set(
+                &DataKeyExt2::UnsettledYield,
+                &(Self::unsettled_yield(env) - pending).max(0),
+            );
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserYieldIndex(user.clone()), &Self::yield_index(env));
+    }
+
+    /// Principal that is expected to sit idle in the contract (i.e. not supplied to Blend).
+    fn idle_principal(env: &Env) -> i128 {
+        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap_or(0);
+        let total = Self::realizable(env, total);
+        let supplied: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::SuppliedToBlend)
+            .unwrap_or(0);
+        (total - supplied).max(0)
+    }
+
+    /// Make sure the idle token balance covers `prize` on top of idle user principal
+    /// and matured withdrawal claims.
+    /// PrizeFund is pure bookkeeping, so without this check a draw could fail with an
+    /// opaque token error or pay the winner out of depositors' money.
+    fn ensure_prize_liquidity(env: &Env, prize: i128) {
+        let token_id = Self::token_id(env);
+        let token_client = token::Client::new(env, &token_id);
+        let self_addr = env.current_contract_address();
+
+        let reserved: i128 = env.storage().instance().get(&DataKey::ReservedForClaims).unwrap_or(0);
+        let required = prize + Self::idle_principal(env) + reserved;
+        let idle = token_client.balance(&self_addr);
+        if idle >= required {
+            return;
+        }
+
+        let auto_harvest: bool = env.storage().instance().get(&DataKey::AutoHarvest).unwrap_or(false);
+        let blend_pool: Option<Address> = env.storage().instance().get(&DataKey::BlendPool);
+        let blend_pool = match blend_pool {
+            Some(pool) if auto_harvest => pool,
+            _ => panic_with_error!(env, Error::InsufficientLiquidityForPrize),
+        };
+
+        let shortfall = required - idle;
+        let received = Self::withdraw_from_blend_pool(env, &blend_pool, &token_id, shortfall);
+        let idle_after = idle + received;
+
+        Self::add_supplied(env, &blend_pool, -received);
+        Self::emit_blend_event(env, symbol_short!("withdraw"), &blend_pool, shortfall, received, 0);
+
+        if idle_after < prize + Self::idle_principal(env) + reserved {
+            panic_with_error!(env, Error::InsufficientLiquidityForPrize);
+        }
+    }
 }
 
 #[cfg(test)]
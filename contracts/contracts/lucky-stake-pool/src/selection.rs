@@ -0,0 +1,30 @@
+//! Weighted winner selection, kept free of storage so it can be tested on its own.
+//!
+//! Participants own consecutive ranges of tickets in list order: the first owns
+//! `[0, t0)`, the second `[t0, t0 + t1)` and so on. A draw reduces its random number
+//! modulo the total to a winning ticket and walks the ranges to its owner.
+
+use soroban_sdk::{Address, Vec};
+
+/// Index of the winning participant and the winning ticket for `random`.
+/// Panics when nobody holds a ticket.
+pub(crate) fn select_winner(participants: &Vec<(Address, i128)>, random: u128) -> (u32, i128) {
+    let total: i128 = participants.iter().map(|(_, tickets)| tickets).sum();
+    assert!(total > 0, "no tickets to draw from");
+    let ticket = (random % total as u128) as i128;
+    (locate_ticket(participants, ticket).0, ticket)
+}
+
+/// Index of the participant owning `ticket` and the `[start, end)` range it owns.
+/// Tickets past the total belong to the last participant.
+pub(crate) fn locate_ticket(participants: &Vec<(Address, i128)>, ticket: i128) -> (u32, i128, i128) {
+    let mut start: i128 = 0;
+    for (i, (_, tickets)) in participants.iter().enumerate() {
+        if ticket < start + tickets {
+            return (i as u32, start, start + tickets);
+        }
+        start += tickets;
+    }
+    let (_, tickets) = participants.last().unwrap();
+    (participants.len() - 1, start - tickets, start)
+}
@@ -0,0 +1,193 @@
+//! Authorization coverage: which address must sign each entrypoint.
+//!
+//! The rest of the suite runs under `mock_all_auths`, which would keep passing if a
+//! `require_auth` were dropped. Here every privileged call is made twice with a single
+//! exact `mock_auths` entry: signed by a non-admin it must fail, signed by the admin it
+//! must succeed. User entrypoints are checked through `env.auths()`.
+
+use super::*;
+use soroban_sdk::testutils::{AuthorizedFunction, MockAuth, MockAuthInvoke};
+
+/// Authorize exactly `signer` calling `fn_name(args)` on the pool, nothing else.
+fn mock_signer(env: &Env, contract_id: &Address, signer: &Address, fn_name: &str, args: Vec<Val>) {
+    env.mock_auths(&[MockAuth {
+        address: signer,
+        invoke: &MockAuthInvoke {
+            contract: contract_id,
+            fn_name,
+            args,
+            sub_invokes: &[],
+        },
+    }]);
+}
+
+/// `$try_fn` must be rejected when signed by `$user` and accepted when signed by `$admin`.
+macro_rules! assert_admin_only {
+    ($env:expr, $c:expr, $admin:expr, $user:expr, $fn_name:ident, $try_fn:ident, [$($arg:expr),*]) => {{
+        let args: Vec<Val> = soroban_sdk::vec![&$env $(, $arg.into_val(&$env))*];
+        mock_signer(&$env, &$c.address, &$user, stringify!($fn_name), args.clone());
+        assert!(
+            $c.$try_fn($(&$arg),*).is_err(),
+            concat!(stringify!($fn_name), " accepted a non-admin signature")
+        );
+        mock_signer(&$env, &$c.address, &$admin, stringify!($fn_name), args);
+        assert!(
+            $c.$try_fn($(&$arg),*).is_ok(),
+            concat!(stringify!($fn_name), " rejected the admin signature")
+        );
+    }};
+}
+
+/// The only authorization recorded for the last call is `signer` signing `fn_name` on
+/// the pool (sub-invocations such as token transfers hang off that root).
+fn assert_signed_by(env: &Env, contract_id: &Address, signer: &Address, fn_name: &str) {
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1, "expected a single signer for {fn_name}");
+    let (address, invocation) = &auths[0];
+    assert_eq!(address, signer);
+    match &invocation.function {
+        AuthorizedFunction::Contract((contract, function, _)) => {
+            assert_eq!(contract, contract_id);
+            assert_eq!(*function, Symbol::new(env, fn_name));
+        }
+        _ => panic!("unexpected root invocation for {fn_name}"),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  Admin configuration
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_auth_admin_setters() {
+    let (env, contract_id, _, admin, user1, _) = setup(7);
+    let c = client(&env, &contract_id);
+    let other = Address::generate(&env);
+
+    assert_admin_only!(env, c, admin, user1, set_snapshot_offset, try_set_snapshot_offset, [3_600u64]);
+    assert_admin_only!(env, c, admin, user1, set_test_seed, try_set_test_seed, [None::<BytesN<32>>]);
+    assert_admin_only!(env, c, admin, user1, set_min_participants, try_set_min_participants, [2u32]);
+    assert_admin_only!(env, c, admin, user1, set_min_draw_interval, try_set_min_draw_interval, [3_600u64]);
+    assert_admin_only!(env, c, admin, user1, set_draw_override, try_set_draw_override, [true]);
+    assert_admin_only!(env, c, admin, user1, set_harvest_policy, try_set_harvest_policy, [1i128, 0u64, 0u32]);
+    assert_admin_only!(env, c, admin, user1, set_yield_split, try_set_yield_split, [5_000u32]);
+    assert_admin_only!(env, c, admin, user1, set_blend_request_type, try_set_blend_request_type, [BLEND_SUPPLY_COLLATERAL]);
+    assert_admin_only!(env, c, admin, user1, set_force_blend_supply, try_set_force_blend_supply, [true]);
+    assert_admin_only!(env, c, admin, user1, set_receipt_token, try_set_receipt_token, [other]);
+    assert_admin_only!(env, c, admin, user1, set_auto_harvest, try_set_auto_harvest, [true]);
+}
+
+#[test]
+fn test_auth_admin_blend_management() {
+    let (env, contract_id, _, admin, user1, _) = setup(7);
+    let c = client(&env, &contract_id);
+    let (blend_a, blend_b) = (register_mock_blend(&env), register_mock_blend(&env));
+    let weights: Map<Address, u32> = Map::from_array(&env, [(blend_a.clone(), 10_000u32), (blend_b.clone(), 0u32)]);
+    // Idle principal for supply_to_blend below
+    c.deposit(&user1, &100_000_000i128);
+
+    assert_admin_only!(env, c, admin, user1, set_blend_pool, try_set_blend_pool, [blend_a]);
+    assert_admin_only!(env, c, admin, user1, add_blend_pool, try_add_blend_pool, [blend_b, 0u32]);
+    assert_admin_only!(env, c, admin, user1, set_blend_weights, try_set_blend_weights, [weights]);
+    assert_admin_only!(env, c, admin, user1, remove_blend_pool, try_remove_blend_pool, [blend_b]);
+
+    assert_admin_only!(env, c, admin, user1, supply_to_blend, try_supply_to_blend, [50_000_000i128]);
+    assert_admin_only!(env, c, admin, user1, rebalance, try_rebalance, [0u32]);
+    assert_admin_only!(env, c, admin, user1, withdraw_from_blend, try_withdraw_from_blend, [10_000_000i128, 0i128]);
+
+    env.mock_all_auths();
+    accrue_blend_yield(&env, &blend_a, &c.get_token(), &contract_id, 1_000_000i128);
+    assert_admin_only!(env, c, admin, user1, harvest_yield, try_harvest_yield, [1_000_000i128, 0i128]);
+    assert_admin_only!(env, c, admin, user1, recognize_loss, try_recognize_loss, [1i128]);
+    assert_admin_only!(env, c, admin, user1, migrate_blend_pool, try_migrate_blend_pool, [blend_b, 0i128]);
+}
+
+#[test]
+fn test_auth_admin_prize_and_draw() {
+    let (env, contract_id, _, admin, user1, _) = setup(7);
+    let c = client(&env, &contract_id);
+    c.deposit(&user1, &100_000_000i128);
+    c.add_prize(&10_000_000i128);
+    assert_signed_by(&env, &contract_id, &admin, "add_prize");
+
+    assert_admin_only!(env, c, admin, user1, execute_draw, try_execute_draw, []);
+    assert_admin_only!(env, c, admin, user1, withdraw_admin_yield, try_withdraw_admin_yield, [user1, 1_000_000i128]);
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  User entrypoints
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_auth_user_entrypoints_signed_by_user() {
+    let (env, contract_id, _, _, user1, user2) = setup(7);
+    let c = client(&env, &contract_id);
+
+    c.deposit(&user1, &100_000_000i128);
+    assert_signed_by(&env, &contract_id, &user1, "deposit");
+    c.withdraw(&user1, &10_000_000i128);
+    assert_signed_by(&env, &contract_id, &user1, "withdraw");
+    c.request_withdrawal(&user1, &10_000_000i128);
+    assert_signed_by(&env, &contract_id, &user1, "request_withdrawal");
+    c.process_queue(&10u32);
+    assert_eq!(env.auths().len(), 0);
+    c.claim_queued(&user1);
+    assert_signed_by(&env, &contract_id, &user1, "claim_queued");
+
+    c.transfer(&user1, &user2, &10_000_000i128);
+    assert_signed_by(&env, &contract_id, &user1, "transfer");
+    c.approve(&user1, &user2, &10_000_000i128, &1_000u32);
+    assert_signed_by(&env, &contract_id, &user1, "approve");
+    c.transfer_from(&user2, &user1, &user2, &10_000_000i128);
+    assert_signed_by(&env, &contract_id, &user2, "transfer_from");
+}
+
+#[test]
+fn test_auth_user_entrypoints_reject_other_signers() {
+    let (env, contract_id, _, admin, user1, user2) = setup(7);
+    let c = client(&env, &contract_id);
+    c.deposit(&user1, &100_000_000i128);
+
+    let amount = 10_000_000i128;
+    for (fn_name, signer) in [("withdraw", &user2), ("withdraw", &admin), ("request_withdrawal", &user2)] {
+        let args = soroban_sdk::vec![&env, user1.into_val(&env), amount.into_val(&env)];
+        mock_signer(&env, &contract_id, signer, fn_name, args);
+        let res = if fn_name == "withdraw" {
+            c.try_withdraw(&user1, &amount).is_err()
+        } else {
+            c.try_request_withdrawal(&user1, &amount).is_err()
+        };
+        assert!(res, "{fn_name} accepted a signature from someone else");
+    }
+
+    let args = soroban_sdk::vec![&env, user1.into_val(&env), user2.into_val(&env), amount.into_val(&env)];
+    mock_signer(&env, &contract_id, &user2, "transfer", args);
+    assert!(c.try_transfer(&user1, &user2, &amount).is_err());
+
+    // transfer_from needs the spender, not the owner
+    let args = soroban_sdk::vec![
+        &env,
+        user2.into_val(&env),
+        user1.into_val(&env),
+        user2.into_val(&env),
+        amount.into_val(&env)
+    ];
+    mock_signer(&env, &contract_id, &user1, "transfer_from", args);
+    assert!(c.try_transfer_from(&user2, &user1, &user2, &amount).is_err());
+}
+
+#[test]
+fn test_auth_harvest_requires_caller() {
+    let (env, contract_id, token_id, _, user1, user2) = setup(7);
+    let c = client(&env, &contract_id);
+    let blend = setup_with_blend_supply(&env, &contract_id, &user1, 100_000_000i128);
+    c.set_harvest_policy(&1_000_000i128, &0u64, &100u32);
+    accrue_blend_yield(&env, &blend, &token_id, &contract_id, 1_000_000i128);
+
+    // The caller earns the reward, so they must sign for themselves
+    let args = soroban_sdk::vec![&env, user2.into_val(&env)];
+    mock_signer(&env, &contract_id, &user1, "harvest", args.clone());
+    assert!(c.try_harvest(&user2).is_err());
+    mock_signer(&env, &contract_id, &user2, "harvest", args);
+    assert!(c.try_harvest(&user2).is_ok());
+}
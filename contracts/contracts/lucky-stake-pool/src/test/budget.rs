@@ -0,0 +1,148 @@
+//! Resource ceilings for the hot paths at growing pool sizes. Native test execution
+//! under-counts CPU compared to the WASM build, so these are regression guards rather
+//! than network limits: raise a ceiling only together with the change that needs it.
+
+use super::*;
+use soroban_sdk::testutils::EnvTestConfig;
+
+#[derive(Debug)]
+struct Cost {
+    cpu: u64,
+    /// Distinct ledger entries read or written by the call.
+    entries: usize,
+}
+
+static MEASUREMENTS: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(1);
+
+/// Run `f` with a fresh budget and footprint and return what it consumed. The native
+/// host meters every access against all ledger entries the test created, where a
+/// network transaction loads only its footprint: a first run finds the footprint, the
+/// ledger is put back, and the measured run sees just those entries. Both runs draw
+/// the same randomness, so they touch the same entries.
+fn measure<T>(env: &Env, f: impl Fn() -> T) -> Cost {
+    let host = env.host();
+    let budget = host.budget_cloned();
+    let ledger = host.with_mut_storage(|s| Ok(s.map.clone())).unwrap();
+    // A fresh seed per call: reusing one would replay earlier auth nonces
+    let seed = [MEASUREMENTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed); 32];
+    host.set_base_prng_seed(seed).unwrap();
+    host.with_mut_storage(|s| {
+        s.footprint = Default::default();
+        Ok(())
+    })
+    .unwrap();
+    f();
+    host.with_mut_storage(|s| {
+        let keys: std::vec::Vec<_> = s.footprint.0.keys(&budget)?.cloned().collect();
+        s.map = Default::default();
+        for (k, v) in ledger.iter(&budget)?.filter(|(k, _)| keys.contains(k)) {
+            s.map = s.map.insert(k.clone(), v.clone(), &budget)?;
+        }
+        s.footprint = Default::default();
+        Ok(())
+    })
+    .unwrap();
+
+    env.budget().reset_unlimited();
+    host.set_base_prng_seed(seed).unwrap();
+    f();
+    let cost = Cost {
+        cpu: env.budget().cpu_instruction_cost(),
+        entries: host.with_mut_storage(|s| Ok(s.footprint.0.len())).unwrap(),
+    };
+
+    // Fold the measured run's writes back into the full ledger
+    host.with_mut_storage(|s| {
+        let mut merged = ledger.clone();
+        for (k, v) in s.map.iter(&budget)? {
+            merged = merged.insert(k.clone(), v.clone(), &budget)?;
+        }
+        s.map = merged;
+        Ok(())
+    })
+    .unwrap();
+    cost
+}
+
+fn assert_within(function: &str, depositors: usize, cost: Cost, max_cpu: u64, max_entries: usize) {
+    assert!(
+        cost.cpu <= max_cpu,
+        "{function} with {depositors} depositors used {} CPU instructions (ceiling {max_cpu})",
+        cost.cpu
+    );
+    assert!(
+        cost.entries <= max_entries,
+        "{function} with {depositors} depositors touched {} ledger entries (ceiling {max_entries})",
+        cost.entries
+    );
+}
+
+/// A weekly pool with `depositors` funded depositors and a prize, ready to draw.
+fn setup_pool(depositors: usize) -> (Env, Address, std::vec::Vec<Address>) {
+    // Setting up hundreds of users is not what we are measuring
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    let (env, contract_id, token_id, _, _, _) = setup_in(env, 7);
+    env.budget().reset_unlimited();
+    let c = client(&env, &contract_id);
+    let users: std::vec::Vec<Address> = (0..depositors).map(|_| new_user(&env, &token_id)).collect();
+    for user in &users {
+        c.deposit(user, &10_000_000i128);
+    }
+    c.add_prize(&10_000_000i128);
+    (env, contract_id, users)
+}
+
+/// (CPU instructions, ledger entries) ceilings of deposit and withdraw. Both touch only
+/// the caller's entries and the pool totals, so they hold at every pool size.
+const DEPOSIT: (u64, usize) = (1_050_000, 14);
+const WITHDRAW: (u64, usize) = (830_000, 11);
+
+/// `draw` is the ceiling of an execute_draw through the index, whose binary search grows
+/// with the logarithm of the pool.
+fn check_costs(depositors: usize, draw: (u64, usize)) {
+    let (env, contract_id, users) = setup_pool(depositors);
+    let c = client(&env, &contract_id);
+
+    let cost = measure(&env, || c.deposit(&users[0], &10_000_000i128));
+    assert_within("deposit", depositors, cost, DEPOSIT.0, DEPOSIT.1);
+
+    let cost = measure(&env, || c.withdraw(&users[0], &5_000_000i128));
+    assert_within("withdraw", depositors, cost, WITHDRAW.0, WITHDRAW.1);
+
+    advance_time(&env, 7 * 86_400);
+    let mut next = 0;
+    while !c.is_index_ready() {
+        next = c.rebuild_index(&next, &MAX_LINEAR_DRAW_DEPOSITORS);
+    }
+    let cost = measure(&env, || c.execute_draw());
+    assert_within("execute_draw", depositors, cost, draw.0, draw.1);
+}
+
+#[test]
+fn test_costs_1_depositor() {
+    check_costs(1, (1_250_000, 16));
+}
+
+#[test]
+fn test_costs_50_depositors() {
+    check_costs(50, (1_450_000, 16));
+}
+
+#[test]
+fn test_costs_200_depositors() {
+    check_costs(200, (1_450_000, 16));
+}
+
+/// Without an index a draw scans every depositor; the scan limit keeps it bounded.
+#[test]
+fn test_costs_linear_draw_at_limit() {
+    let depositors = MAX_LINEAR_DRAW_DEPOSITORS as usize;
+    let (env, contract_id, _) = setup_pool(depositors);
+    let c = client(&env, &contract_id);
+    advance_time(&env, 7 * 86_400);
+    assert!(!c.is_index_ready());
+    let cost = measure(&env, || c.execute_draw());
+    assert_within("linear execute_draw", depositors, cost, 3_350_000, 100);
+}
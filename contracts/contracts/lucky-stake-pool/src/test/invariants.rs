@@ -0,0 +1,118 @@
+//! Property-based checks of the pool's bookkeeping over random deposit / withdraw /
+//! prize / draw sequences. Every step must succeed exactly when the model says it is
+//! valid, and the invariants in `check_invariants` must hold after each one.
+
+use super::*;
+use proptest::prelude::*;
+use soroban_sdk::testutils::EnvTestConfig;
+
+const USERS: usize = 3;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Deposit { user: usize, amount: i128 },
+    /// Withdraw `bps` of the user's balance; above 10_000 bps overdraws and must fail.
+    Withdraw { user: usize, bps: i128 },
+    AddPrize { amount: i128 },
+    Draw,
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (0..USERS, 1i128..=10_000_000_000).prop_map(|(user, amount)| Op::Deposit { user, amount }),
+        3 => (0..USERS, 0i128..=12_000).prop_map(|(user, bps)| Op::Withdraw { user, bps }),
+        2 => (1i128..=1_000_000_000).prop_map(|amount| Op::AddPrize { amount }),
+        1 => Just(Op::Draw),
+    ]
+}
+
+struct Harness {
+    env: Env,
+    contract_id: Address,
+    token_id: Address,
+    users: std::vec::Vec<Address>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        // Hundreds of environments per run: skip the per-Env test snapshot
+        let env = Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        });
+        let (env, contract_id, token_id, _, user1, user2) = setup_in(env, 7);
+        // The budget accumulates over the whole sequence; costs are budget.rs's job
+        env.budget().reset_unlimited();
+        let user3 = new_user(&env, &token_id);
+        Harness {
+            env,
+            contract_id,
+            token_id,
+            users: std::vec![user1, user2, user3],
+        }
+    }
+
+    fn client(&self) -> LuckyStakePoolClient<'_> {
+        client(&self.env, &self.contract_id)
+    }
+
+    /// Apply `op` and check that it succeeded exactly when it was valid.
+    fn apply(&self, op: &Op) -> Result<(), TestCaseError> {
+        let c = self.client();
+        match *op {
+            Op::Deposit { user, amount } => {
+                prop_assert!(c.try_deposit(&self.users[user], &amount).is_ok(), "deposit failed");
+            }
+            Op::Withdraw { user, bps } => {
+                let user = &self.users[user];
+                let balance = c.get_balance(user);
+                let amount = balance * bps / 10_000;
+                let valid = amount > 0 && amount <= balance;
+                prop_assert_eq!(c.try_withdraw(user, &amount).is_ok(), valid, "withdraw {} of {}", amount, balance);
+            }
+            Op::AddPrize { amount } => {
+                prop_assert!(c.try_add_prize(&amount).is_ok(), "add_prize failed");
+            }
+            Op::Draw => {
+                advance_time(&self.env, 7 * 86_400);
+                let valid = c.get_prize_fund() > 0 && c.get_total_tickets() > 0;
+                prop_assert_eq!(c.try_execute_draw().is_ok(), valid, "execute_draw");
+            }
+        }
+        Ok(())
+    }
+
+    fn check_invariants(&self) -> Result<(), TestCaseError> {
+        let c = self.client();
+        let (mut balances, mut tickets) = (0i128, 0i128);
+        for user in &self.users {
+            let (balance, user_tickets) = (c.get_balance(user), c.get_tickets(user));
+            prop_assert!(balance >= 0 && user_tickets >= 0);
+            if balance == 0 {
+                prop_assert_eq!(user_tickets, 0, "tickets left without a balance");
+            }
+            balances += balance;
+            tickets += user_tickets;
+        }
+        let total = c.get_total_deposits();
+        prop_assert_eq!(total, balances, "TotalDeposits != sum of balances");
+        prop_assert_eq!(c.get_total_tickets(), tickets, "TotalTickets != sum of tickets");
+        prop_assert!(
+            token_balance(&self.env, &self.token_id, &self.contract_id) >= total + c.get_prize_fund(),
+            "contract holds less than deposits + prize fund"
+        );
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_bookkeeping_invariants(ops in prop::collection::vec(op_strategy(), 1..40)) {
+        let h = Harness::new();
+        for op in &ops {
+            h.apply(op)?;
+            h.check_invariants()?;
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Declarative setup for multi-user, multi-epoch scenarios. A `ScenarioBuilder` describes
+//! the pool (period, mock Blend, yield split, seed) and the users with their opening
+//! deposits; `build` deploys it in one go and the resulting
+//! `Scenario` drives epochs forward and checks `assert_invariants` along the way.
+
+use super::*;
+use soroban_sdk::BytesN;
+
+pub(super) struct ScenarioBuilder {
+    period_days: u32,
+    users: usize,
+    deposits: std::vec::Vec<(usize, i128)>,
+    blend: bool,
+    yield_split: Option<u32>,
+    min_participants: Option<u32>,
+    prize: i128,
+    seed: Option<u8>,
+}
+
+impl ScenarioBuilder {
+    /// A 7-day pool with two funded users and nothing else configured.
+    pub(super) fn new() -> Self {
+        ScenarioBuilder {
+            period_days: 7,
+            users: 2,
+            deposits: std::vec::Vec::new(),
+            blend: false,
+            yield_split: None,
+            min_participants: None,
+            prize: 0,
+            seed: None,
+        }
+    }
+
+    pub(super) fn period_days(mut self, days: u32) -> Self {
+        self.period_days = days;
+        self
+    }
+
+    /// Number of users, each funded with 100,000 XLM.
+    pub(super) fn users(mut self, count: usize) -> Self {
+        self.users = count;
+        self
+    }
+
+    /// Opening deposit of `amount` for user `user`, made after the pool is configured.
+    pub(super) fn deposit(mut self, user: usize, amount: i128) -> Self {
+        self.deposits.push((user, amount));
+        self
+    }
+
+    /// Register a mock Blend pool before the deposits, so they are supplied to it.
+    pub(super) fn blend(mut self) -> Self {
+        self.blend = true;
+        self
+    }
+
+    pub(super) fn yield_split(mut self, prize_bps: u32) -> Self {
+        self.yield_split = Some(prize_bps);
+        self
+    }
+
+    pub(super) fn min_participants(mut self, count: u32) -> Self {
+        self.min_participants = Some(count);
+        self
+    }
+
+    /// Opening prize fund, added by the admin.
+    pub(super) fn prize(mut self, amount: i128) -> Self {
+        self.prize = amount;
+        self
+    }
+
+    /// Take every draw's randomness from `[byte; 32]` (see `set_test_seed`).
+    pub(super) fn seed(mut self, byte: u8) -> Self {
+        self.seed = Some(byte);
+        self
+    }
+
+    pub(super) fn build(self) -> Scenario {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Scenarios run many calls on one Env; costs are budget.rs's job
+        env.budget().reset_unlimited();
+
+        let token_id = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        let admin = new_user(&env, &token_id);
+        let users: std::vec::Vec<Address> = (0..self.users).map(|_| new_user(&env, &token_id)).collect();
+        let contract_id = env.register_contract(None, LuckyStakePool);
+        let c = client(&env, &contract_id);
+        c.initialize(&admin, &token_id, &self.period_days);
+
+        let blend = self.blend.then(|| {
+            let blend = register_mock_blend(&env);
+            c.set_blend_pool(&blend);
+            blend
+        });
+
+        for (user, amount) in self.deposits {
+            c.deposit(&users[user], &amount);
+        }
+        if let Some(prize_bps) = self.yield_split {
+            c.set_yield_split(&prize_bps);
+        }
+        if let Some(count) = self.min_participants {
+            c.set_min_participants(&count);
+        }
+        if self.prize > 0 {
+            c.add_prize(&self.prize);
+        }
+        if let Some(byte) = self.seed {
+            c.set_test_seed(&Some(BytesN::from_array(&env, &[byte; 32])));
+        }
+
+        Scenario {
+            env,
+            contract_id,
+            token_id,
+            users,
+            blend,
+            period_days: self.period_days,
+        }
+    }
+}
+
+pub(super) struct Scenario {
+    pub(super) env: Env,
+    pub(super) contract_id: Address,
+    pub(super) token_id: Address,
+    pub(super) users: std::vec::Vec<Address>,
+    pub(super) blend: Option<Address>,
+    period_days: u32,
+}
+
+impl Scenario {
+    pub(super) fn client(&self) -> LuckyStakePoolClient<'_> {
+        client(&self.env, &self.contract_id)
+    }
+
+    pub(super) fn user(&self, i: usize) -> &Address {
+        &self.users[i]
+    }
+
+    /// Add a funded user the builder did not know about; returns its index.
+    pub(super) fn add_user(&mut self) -> usize {
+        self.users.push(new_user(&self.env, &self.token_id));
+        self.users.len() - 1
+    }
+
+    /// Token balance of user `i`'s wallet.
+    pub(super) fn wallet(&self, i: usize) -> i128 {
+        token_balance(&self.env, &self.token_id, &self.users[i])
+    }
+
+    pub(super) fn advance(&self, seconds: u64) {
+        advance_time(&self.env, seconds);
+    }
+
+    /// Move the clock forward one draw period.
+    pub(super) fn advance_period(&self) {
+        self.advance(self.period_days as u64 * 86_400);
+    }
+
+    /// Accrue `amount` of interest in the mock Blend and harvest all of it.
+    pub(super) fn harvest(&self, amount: i128) {
+        let blend = self.blend.as_ref().expect("scenario has no Blend pool");
+        accrue_blend_yield(&self.env, blend, &self.token_id, &self.contract_id, amount);
+        self.client().harvest_yield(&amount, &amount);
+    }
+
+    /// Wipe `amount` off the pool's Blend position without recognizing it.
+    pub(super) fn slash(&self, amount: i128) {
+        let blend = self.blend.as_ref().expect("scenario has no Blend pool");
+        mock_blend::MockBlendClient::new(&self.env, blend).slash(&self.contract_id, &amount);
+    }
+
+    /// Wait out the period, run the draw and return what it recorded.
+    pub(super) fn draw(&self) -> DrawResult {
+        self.advance_period();
+        let c = self.client();
+        let nonce = c.get_draw_nonce();
+        c.execute_draw();
+        c.get_draw_result(&nonce).expect("draw recorded no result")
+    }
+
+    pub(super) fn assert_invariants(&self) {
+        assert_invariants(&self.env, &self.contract_id, &self.users);
+    }
+}
+
+/// Bookkeeping that has to hold between any two calls, given every address that ever
+/// deposited into `pool`: settled balances add up to `TotalDeposits` less the unsettled
+/// yield, which covers every user's pending share, no user holds tickets
+/// without a position and tickets stay within `TotalTickets`.
+pub(super) fn assert_invariants(env: &Env, pool: &Address, users: &[Address]) {
+    let c = client(env, pool);
+    let (mut settled, mut pending, mut tickets) = (0i128, 0i128, 0i128);
+    for user in users {
+        let balance = c.get_balance(user) + c.get_pending_yield(user);
+        let user_tickets = c.get_tickets(user);
+        assert!(balance >= 0 && user_tickets >= 0, "negative position for {user:?}");
+        if balance == 0 {
+            assert_eq!(user_tickets, 0, "tickets left without a balance");
+        }
+        settled += c.get_balance(user);
+        pending += c.get_pending_yield(user);
+        tickets += user_tickets;
+    }
+    let total = c.get_total_deposits();
+    let unsettled: i128 = env.as_contract(pool, || {
+        env.storage().instance().get(&DataKeyExt2::UnsettledYield).unwrap_or(0)
+    });
+    assert_eq!(settled, total - unsettled, "settled balances off TotalDeposits");
+    assert!(pending <= unsettled, "pending yield {pending} exceeds unsettled {unsettled}");
+    assert!(tickets <= c.get_total_tickets(), "tickets exceed TotalTickets");
+}
+
+/// Three users and four seeded draws: every draw takes the 15% admin share and pays the
+/// rest to the winner.
+#[test]
+fn test_scenario_pool_over_four_epochs() {
+    let mut s = ScenarioBuilder::new()
+        .seed(7)
+        .deposit(0, 100_000_000)
+        .deposit(1, 300_000_000)
+        .build();
+    let c = s.client();
+    assert_eq!(c.get_tickets(s.user(0)), 100_000_000 * 7);
+    assert_eq!(c.get_tickets(s.user(1)), 300_000_000 * 7);
+
+    let mut fees = 0;
+    for (epoch, prize) in [10_000_000i128, 20_000_000, 30_000_000, 40_000_000].into_iter().enumerate() {
+        if epoch == 1 {
+            let late = s.add_user();
+            s.client().deposit(&s.users[late], &200_000_000i128);
+        }
+        if epoch == 2 {
+            s.client().withdraw(s.user(1), &300_000_000i128);
+        }
+        let c = s.client();
+        c.add_prize(&prize);
+        s.advance_period();
+        let before: std::vec::Vec<i128> = (0..s.users.len()).map(|i| s.wallet(i)).collect();
+
+        let winner = c.execute_draw().unwrap();
+        let w = s.users.iter().position(|u| *u == winner).unwrap();
+        let result = c.get_draw_result(&(epoch as u64)).unwrap();
+        assert_eq!(result.prize, prize);
+        let net_prize = prize * 85 / 100;
+        assert_eq!(s.wallet(w) - before[w], net_prize);
+        fees += prize - net_prize;
+        s.assert_invariants();
+    }
+    let c = s.client();
+    assert_eq!(c.get_prize_fund(), 0);
+    let admin_yield: i128 = s.env.as_contract(&s.contract_id, || {
+        s.env.storage().instance().get(&DataKey::AdminYield).unwrap_or(0)
+    });
+    assert_eq!(admin_yield, fees);
+}
+
+/// Yield-funded prizes over several epochs, then a Blend loss: it is haircut, draws carry
+/// on and everyone can still leave with their realizable balance.
+#[test]
+fn test_scenario_harvests_loss_and_exit_over_epochs() {
+    let s = ScenarioBuilder::new()
+        .users(3)
+        .blend()
+        .yield_split(5_000)
+        .seed(3)
+        .deposit(0, 100_000_000)
+        .deposit(1, 200_000_000)
+        .deposit(2, 300_000_000)
+        .build();
+    let c = s.client();
+
+    // Each 40 XLM harvest: 20 to depositors, 20 to the prize
+    for epoch in 0..3u64 {
+        s.harvest(40_000_000);
+        assert_eq!(c.get_prize_fund(), 20_000_000);
+        let result = s.draw();
+        assert_eq!((result.nonce, result.prize), (epoch, 20_000_000));
+        s.assert_invariants();
+    }
+    assert_eq!(c.get_total_deposits(), 660_000_000);
+
+    // 66 XLM is 10% of deposits
+    s.slash(66_000_000);
+    c.recognize_loss(&66_000_000i128);
+    assert_eq!(c.get_haircut_factor(), HAIRCUT_SCALE * 9 / 10);
+    s.assert_invariants();
+
+    s.harvest(40_000_000);
+    assert_eq!(s.draw().nonce, 3);
+    s.assert_invariants();
+
+    c.withdraw_from_blend(&c.get_supplied_to_blend(), &0i128);
+    for i in 0..3 {
+        let balance = c.get_balance(s.user(i)) + c.get_pending_yield(s.user(i));
+        let before = s.wallet(i);
+        c.withdraw(s.user(i), &balance);
+        assert!(s.wallet(i) - before >= balance * 9 / 10 - 1);
+        s.assert_invariants();
+    }
+    // Only the unsettled-yield shortfall stays behind in TotalDeposits
+    assert_eq!((0..3).map(|i| c.get_balance(s.user(i))).sum::<i128>(), 0);
+}
@@ -0,0 +1,104 @@
+//! `select_winner` on its own: range boundaries, extreme ticket counts and how evenly
+//! it spreads random numbers over the tickets.
+
+use super::*;
+use crate::selection::{locate_ticket, select_winner};
+
+fn participants(env: &Env, tickets: &[i128]) -> (std::vec::Vec<Address>, Vec<(Address, i128)>) {
+    let owners: std::vec::Vec<Address> = tickets.iter().map(|_| Address::generate(env)).collect();
+    let mut list = Vec::new(env);
+    for (owner, tickets) in owners.iter().zip(tickets) {
+        list.push_back((owner.clone(), *tickets));
+    }
+    (owners, list)
+}
+
+#[test]
+fn test_select_winner_boundaries_between_participants() {
+    let env = Env::default();
+    let (_, list) = participants(&env, &[10, 20, 30]);
+    assert_eq!(select_winner(&list, 0), (0, 0));
+    assert_eq!(select_winner(&list, 9), (0, 9));
+    assert_eq!(select_winner(&list, 10), (1, 10));
+    assert_eq!(select_winner(&list, 29), (1, 29));
+    assert_eq!(select_winner(&list, 30), (2, 30));
+    assert_eq!(select_winner(&list, 59), (2, 59));
+    // The random number wraps around the total
+    assert_eq!(select_winner(&list, 60), (0, 0));
+    assert_eq!(select_winner(&list, 60 * 1_000 + 15), (1, 15));
+    assert_eq!(locate_ticket(&list, 15), (1, 10, 30));
+}
+
+#[test]
+fn test_select_winner_skips_zero_ticket_participants() {
+    let env = Env::default();
+    let (_, list) = participants(&env, &[0, 5, 0, 5]);
+    assert_eq!(select_winner(&list, 0), (1, 0));
+    assert_eq!(select_winner(&list, 4), (1, 4));
+    assert_eq!(select_winner(&list, 5), (3, 5));
+}
+
+#[test]
+fn test_select_winner_single_participant() {
+    let env = Env::default();
+    let (_, list) = participants(&env, &[7]);
+    for random in [0u128, 6, 7, 1 << 64, u128::MAX] {
+        assert_eq!(select_winner(&list, random).0, 0);
+    }
+}
+
+#[test]
+fn test_select_winner_maximum_ticket_counts() {
+    let env = Env::default();
+    let (_, list) = participants(&env, &[i128::MAX]);
+    assert_eq!(select_winner(&list, u128::MAX), (0, (u128::MAX % i128::MAX as u128) as i128));
+
+    let half = i128::MAX / 2;
+    let (_, list) = participants(&env, &[half, half]);
+    assert_eq!(select_winner(&list, half as u128 - 1), (0, half - 1));
+    assert_eq!(select_winner(&list, half as u128), (1, half));
+    assert_eq!(select_winner(&list, 2 * half as u128 - 1), (1, 2 * half - 1));
+}
+
+#[test]
+#[should_panic(expected = "no tickets to draw from")]
+fn test_select_winner_rejects_an_empty_draw() {
+    let env = Env::default();
+    let (_, list) = participants(&env, &[0, 0]);
+    select_winner(&list, 1);
+}
+
+/// SplitMix64, a fixed stand-in for the ledger PRNG so the test is reproducible.
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[test]
+fn test_select_winner_distribution_matches_weights() {
+    let env = Env::default();
+    let weights = [1_000i128, 2_000, 3_000, 4_000];
+    let (_, list) = participants(&env, &weights);
+    let samples = 20_000u32;
+    let mut wins = [0u32; 4];
+    let mut state = 42u64;
+    for _ in 0..samples {
+        let random = ((splitmix(&mut state) as u128) << 64) | splitmix(&mut state) as u128;
+        wins[select_winner(&list, random).0 as usize] += 1;
+    }
+
+    let total: i128 = weights.iter().sum();
+    let chi_squared: f64 = weights
+        .iter()
+        .zip(wins)
+        .map(|(weight, observed)| {
+            let expected = samples as f64 * *weight as f64 / total as f64;
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum();
+    // 3 degrees of freedom: exceeded with probability 0.001 for a fair selection
+    assert!(chi_squared < 16.27, "chi-squared {chi_squared} for wins {wins:?}");
+}
@@ -0,0 +1,38 @@
+//! Deterministic draws for tests and demo networks.
+//!
+//! This module only exists in test builds and builds with the `deterministic-draws`
+//! feature: a release contract has neither `set_test_seed` nor the seed lookup in the
+//! draw, so no runtime flag can switch production draws to a known seed.
+
+use soroban_sdk::{contractimpl, contracttype, Bytes, BytesN, Env};
+
+use crate::{LuckyStakePool, LuckyStakePoolClient};
+
+#[contracttype]
+enum TestKey {
+    Seed,
+}
+
+#[contractimpl]
+impl LuckyStakePool {
+    /// Make every draw take its randomness from `seed` and the draw nonce alone, so the
+    /// same deposits always produce the same winners. `None` restores the normal sources.
+    pub fn set_test_seed(env: Env, seed: Option<BytesN<32>>) {
+        Self::admin(&env).require_auth();
+        Self::assert_unlocked(&env);
+        match seed {
+            Some(seed) => env.storage().instance().set(&TestKey::Seed, &seed),
+            None => env.storage().instance().remove(&TestKey::Seed),
+        }
+    }
+}
+
+/// `sha256(seed || nonce)` for draw `nonce` when a test seed is set. Its first 8 bytes
+/// pick the winning ticket and the whole hash reseeds the PRNG for the draw's later
+/// samples.
+pub(crate) fn seeded_random(env: &Env, nonce: u64) -> Option<BytesN<32>> {
+    let seed: BytesN<32> = env.storage().instance().get(&TestKey::Seed)?;
+    let mut material = Bytes::from(seed);
+    material.extend_from_array(&nonce.to_be_bytes());
+    Some(env.crypto().sha256(&material).into())
+}